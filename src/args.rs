@@ -1,13 +1,201 @@
 use super::Error;
-use std::{env, fs, iter::Peekable};
+use crate::crossword::{
+    split_directions, split_words, Direction, Fill, Folding, GenOptions, Normalization, SvgOptions,
+};
+use std::{
+    env, fs,
+    io::{self, Read},
+    str::FromStr,
+};
 
 /// The raw form of the word list.
-pub type ArgWords = Peekable<env::Args>;
+pub type ArgWords = Vec<String>;
+
+/// The format crosswords are written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// The text form with colors for the terminal.
+    Text,
+    /// A printable HTML page.
+    Html,
+    /// A vector graphic.
+    Svg,
+    /// The JSON form for other programs.
+    #[cfg(feature = "json")]
+    Json,
+    /// The ipuz format for puzzle apps.
+    #[cfg(feature = "json")]
+    Ipuz,
+}
+
+/// When to use colors and other escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    /// Only if the output is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
 
 /// The command to be executed.
 pub enum Command {
-    Generate { watch: bool, arg_words: ArgWords },
-    Solve { watch: bool, crossword_str: String },
+    Generate {
+        watch: bool,
+        options: GenOptions,
+        arg_words: ArgWords,
+        /// The file to write the crossword to or `None` for stdout.
+        output: Option<String>,
+        format: Format,
+        /// Whether to add an answer key to formats that support it.
+        answer_key: bool,
+        svg_options: SvgOptions,
+        color: ColorMode,
+    },
+    Solve {
+        watch: bool,
+        /// Overrides the crossword's `directions` header if set.
+        directions: Option<Vec<Direction>>,
+        all_occurrences: bool,
+        /// Whether to ignore case, overriding the crossword's `case` header if set.
+        ignore_case: Option<bool>,
+        /// Overrides the crossword's `normalization` header if set, where `Some(None)` turns normalization off.
+        normalization: Option<Option<Normalization>>,
+        /// Overrides the crossword's `fold` header if set.
+        folding: Option<Option<Folding>>,
+        /// Whether to show the characters left over after solving, which spell the hidden message if there is one.
+        message: bool,
+        file_name: String,
+        crossword_str: String,
+        /// The file to write the solution to or `None` for stdout.
+        output: Option<String>,
+        format: Format,
+        /// Whether to add an answer key to formats that support it.
+        answer_key: bool,
+        svg_options: SvgOptions,
+        color: ColorMode,
+    },
+    /// Write a PDF with a page for every crossword.
+    #[cfg(feature = "pdf")]
+    Book {
+        /// Overrides the `directions` header of every crossword if set.
+        directions: Option<Vec<Direction>>,
+        answer_key: bool,
+        /// The TrueType or OpenType font to embed.
+        font: Vec<u8>,
+        /// The file names and contents of the crosswords.
+        inputs: Vec<(String, String)>,
+        /// The file to write the PDF to or `None` for stdout.
+        output: Option<String>,
+    },
+}
+
+/// Reads the whole file or stdin if the file name is `-`.
+fn read_input(file_name: &str) -> Result<String, Error> {
+    if file_name == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|_| "error reading stdin")?;
+        Ok(input)
+    } else {
+        fs::read_to_string(file_name).map_err(|_| "error reading file")
+    }
+}
+
+/// Reads every file in the directory in the order of their names or the file itself if it's not a directory.
+#[cfg(feature = "pdf")]
+fn read_inputs(path: String) -> Result<Vec<(String, String)>, Error> {
+    if path == "-" || !std::path::Path::new(&path).is_dir() {
+        let input = read_input(&path)?;
+        return Ok(vec![(path, input)]);
+    }
+
+    let mut file_names = fs::read_dir(&path)
+        .map_err(|_| "error reading directory")?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        // Skip hidden files such as `.DS_Store`
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    file_names.sort_unstable();
+
+    file_names
+        .into_iter()
+        .map(|file_name| {
+            let input = read_input(&file_name)?;
+            Ok((file_name, input))
+        })
+        .collect()
+}
+
+/// Parses the value given to an option.
+fn parse_value<T: FromStr>(value: Option<String>, err: Error) -> Result<T, Error> {
+    value.and_then(|value| value.parse().ok()).ok_or(err)
+}
+
+/// Parses the size given to `--size`, which is either a preset or a width and height such as `15x15`.
+fn parse_size(size: Option<String>) -> Result<(usize, usize), Error> {
+    let size = size.ok_or("invalid size")?;
+    match size.as_ref() {
+        "small" => Ok((10, 10)),
+        "medium" => Ok((15, 15)),
+        "large" => Ok((20, 20)),
+        "portrait" => Ok((12, 20)),
+        "landscape" => Ok((20, 12)),
+        _ => {
+            let (width, height) = size.split_once(['x', '×']).ok_or("invalid size")?;
+            Ok((
+                parse_value(Some(width.to_string()), "invalid size")?,
+                parse_value(Some(height.to_string()), "invalid size")?,
+            ))
+        }
+    }
+}
+
+/// Parses the format given to `--format`.
+fn parse_format(format: Option<String>) -> Result<Format, Error> {
+    match format.as_deref() {
+        Some("text") => Ok(Format::Text),
+        Some("html") => Ok(Format::Html),
+        Some("svg") => Ok(Format::Svg),
+        #[cfg(feature = "json")]
+        Some("json") => Ok(Format::Json),
+        #[cfg(feature = "json")]
+        Some("ipuz") => Ok(Format::Ipuz),
+        #[cfg(not(feature = "json"))]
+        Some("json" | "ipuz") => Err("JSON and ipuz output require the `json` feature"),
+        _ => Err("invalid format"),
+    }
+}
+
+/// Parses the cell size given to `--cell-size`, which has to be a positive number of pixels.
+fn parse_cell_size(cell_size: Option<String>) -> Result<f64, Error> {
+    let cell_size: f64 = parse_value(cell_size, "invalid cell size")?;
+    if cell_size.is_finite() && cell_size > 0.0 {
+        Ok(cell_size)
+    } else {
+        Err("invalid cell size")
+    }
+}
+
+/// Parses the color mode given to `--color`.
+fn parse_color(color: Option<String>) -> Result<ColorMode, Error> {
+    match color.as_deref() {
+        Some("auto") => Ok(ColorMode::Auto),
+        Some("always") => Ok(ColorMode::Always),
+        Some("never") => Ok(ColorMode::Never),
+        _ => Err("invalid color mode"),
+    }
+}
+
+/// Parses the comma-separated list of directions given to `--directions`.
+fn parse_directions(directions: Option<String>) -> Result<Vec<Direction>, Error> {
+    if let Some(directions) = directions {
+        split_directions(&directions)
+    } else {
+        Err("no directions")
+    }
 }
 
 pub fn parse() -> Result<Command, Error> {
@@ -27,21 +215,197 @@ pub fn parse() -> Result<Command, Error> {
 
     if let Some(arg) = args.next() {
         match arg.as_ref() {
-            "gen" => Ok(Command::Generate { watch, arg_words: args }),
+            "gen" => {
+                let mut options = GenOptions::default();
+                let mut arg_words = ArgWords::new();
+                let mut output = None;
+                let mut format = Format::Text;
+                let mut answer_key = false;
+                let mut svg_options = SvgOptions::default();
+                let mut color = ColorMode::Auto;
+                while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+                    match option.as_ref() {
+                        "--directions" => options.directions = parse_directions(args.next())?,
+                        "--seed" => options.seed = Some(parse_value(args.next(), "invalid seed")?),
+                        "--overlap" => {
+                            options.overlap = parse_value(args.next(), "invalid overlap")?
+                        }
+                        "--width" => {
+                            options.width = Some(parse_value(args.next(), "invalid width")?)
+                        }
+                        "--height" => {
+                            options.height = Some(parse_value(args.next(), "invalid height")?)
+                        }
+                        "--fill" => {
+                            options.fill = Fill::try_from(args.next().unwrap_or_default().as_ref())?
+                        }
+                        "--size" => {
+                            let (width, height) = parse_size(args.next())?;
+                            options.width = Some(width);
+                            options.height = Some(height);
+                        }
+                        "--message" => options.message = Some(args.next().ok_or("no message")?),
+                        "--words" => {
+                            let words_str = read_input(&args.next().ok_or("no file name")?)?;
+                            for line in words_str.lines() {
+                                let words = split_words(line)
+                                    .map_err(|_| "unclosed quote in the word list")?;
+                                arg_words.extend(words.into_iter().map(String::from));
+                            }
+                        }
+                        "--output" => output = Some(args.next().ok_or("no output file name")?),
+                        "--format" => format = parse_format(args.next())?,
+                        "--answer-key" => answer_key = true,
+                        "--color" => color = parse_color(args.next())?,
+                        "--font" => svg_options.font = args.next().ok_or("no font")?,
+                        "--cell-size" => svg_options.cell_size = parse_cell_size(args.next())?,
+                        _ => return Err("invalid option"),
+                    }
+                }
+                if watch && format != Format::Text {
+                    return Err("only text output can be watched");
+                }
+                if answer_key && !matches!(format, Format::Html | Format::Svg) {
+                    return Err("only HTML and SVG output can have an answer key");
+                }
+                for word in args {
+                    if word.starts_with("--") {
+                        return Err("options must come before the words");
+                    }
+                    arg_words.push(word);
+                }
+                Ok(Command::Generate {
+                    watch,
+                    options,
+                    arg_words,
+                    output,
+                    format,
+                    answer_key,
+                    svg_options,
+                    color,
+                })
+            }
             "solve" => {
+                let mut directions = None;
+                let mut all_occurrences = false;
+                let mut ignore_case = None;
+                let mut normalization = None;
+                let mut folding = None;
+                let mut message = false;
+                let mut output = None;
+                let mut format = Format::Text;
+                let mut answer_key = false;
+                let mut svg_options = SvgOptions::default();
+                let mut color = ColorMode::Auto;
+                while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+                    match option.as_ref() {
+                        "--directions" => directions = Some(parse_directions(args.next())?),
+                        "--all-occurrences" => all_occurrences = true,
+                        "--ignore-case" => ignore_case = Some(true),
+                        "--case-sensitive" => ignore_case = Some(false),
+                        "--message" => message = true,
+                        "--normalization" => {
+                            normalization =
+                                Some(match args.next().ok_or("no normalization")?.as_ref() {
+                                    "none" => None,
+                                    normalization => Some(Normalization::try_from(normalization)?),
+                                })
+                        }
+                        "--fold" => {
+                            folding = Some(match args.next().ok_or("no folding")?.as_ref() {
+                                "none" => None,
+                                folding => Some(Folding::try_from(folding)?),
+                            })
+                        }
+                        "--output" => output = Some(args.next().ok_or("no output file name")?),
+                        "--format" => format = parse_format(args.next())?,
+                        "--answer-key" => answer_key = true,
+                        "--color" => color = parse_color(args.next())?,
+                        "--font" => svg_options.font = args.next().ok_or("no font")?,
+                        "--cell-size" => svg_options.cell_size = parse_cell_size(args.next())?,
+                        _ => return Err("invalid option"),
+                    }
+                }
+                if watch && format != Format::Text {
+                    return Err("only text output can be watched");
+                }
+                if answer_key && !matches!(format, Format::Html | Format::Svg) {
+                    return Err("only HTML and SVG output can have an answer key");
+                }
+                if message && format != Format::Text {
+                    return Err("only text output can show the hidden message");
+                }
                 if let Some(file_name) = args.next() {
-                    if let Ok(crossword_str) = fs::read_to_string(file_name) {
-                        Ok(Command::Solve { watch, crossword_str })
-                    } else {
-                        Err("error reading file")
+                    if args.any(|arg| arg.starts_with("--")) {
+                        return Err("options must come before the file name");
                     }
+                    let crossword_str = read_input(&file_name)?;
+                    Ok(Command::Solve {
+                        watch,
+                        directions,
+                        all_occurrences,
+                        ignore_case,
+                        normalization,
+                        folding,
+                        message,
+                        file_name,
+                        crossword_str,
+                        output,
+                        format,
+                        answer_key,
+                        svg_options,
+                        color,
+                    })
                 } else {
                     Err("no file name")
                 }
             }
+            #[cfg(feature = "pdf")]
+            "book" => {
+                let mut directions = None;
+                let mut answer_key = false;
+                let mut font = None;
+                let mut output = None;
+                while let Some(option) = args.next_if(|arg| arg.starts_with("--")) {
+                    match option.as_ref() {
+                        "--directions" => directions = Some(parse_directions(args.next())?),
+                        "--answer-key" => answer_key = true,
+                        "--font-file" => {
+                            let font_file = args.next().ok_or("no font file name")?;
+                            font =
+                                Some(fs::read(font_file).map_err(|_| "error reading font file")?);
+                        }
+                        "--output" => output = Some(args.next().ok_or("no output file name")?),
+                        _ => return Err("invalid option"),
+                    }
+                }
+                if watch {
+                    return Err("books cannot be watched");
+                }
+                let font = font.ok_or("no font file, which is required to write a PDF")?;
+                let mut inputs = Vec::new();
+                for path in args {
+                    if path.starts_with("--") {
+                        return Err("options must come before the file names");
+                    }
+                    inputs.extend(read_inputs(path)?);
+                }
+                if inputs.is_empty() {
+                    return Err("no file name");
+                }
+                Ok(Command::Book {
+                    directions,
+                    answer_key,
+                    font,
+                    inputs,
+                    output,
+                })
+            }
+            #[cfg(not(feature = "pdf"))]
+            "book" => Err("the `book` command requires the `pdf` feature"),
             _ => Err("invalid command"),
         }
     } else {
-        Err("* Use `gen` followed by a list of words as the arguments to generate a crossword containing those words.\n  Use `watch gen` followed by words to watch a crossword being made out of those words.\n* Use `solve` followed by the path to a crossword as the arguments to solve a crossword.\n  Use `watch solve` followed by a crossword to watch it being solved.\nExample: `crustword watch solve crosswords/generated`")
+        Err("* Use `gen` followed by a list of words as the arguments to generate a crossword containing those words.\n  Use `watch gen` followed by words to watch a crossword being made out of those words.\n* Use `solve` followed by the path to a crossword as the arguments to solve a crossword.\n  Use `-` as the path to read the crossword from stdin.\n  Use `watch solve` followed by a crossword to watch it being solved.\n* Put `--directions` followed by a comma-separated list such as `east,south,southeast` after `gen` or `solve` to only allow those directions.\n  Generated crosswords record them in a `directions` header, which `solve` and `book` follow unless given `--directions`.\n* Put `--seed` followed by a number after `gen` to generate the same crossword every time.\n* Put `--overlap` followed by a number from 0 to 1 after `gen` to control how much words cross each other.\n* Put `--width` and `--height` followed by a number after `gen` to set the size of the grid.\n  Use `--size` followed by `small`, `medium`, `large`, `portrait`, `landscape` or a size such as `15x15` to set both at once.\n* Put `--fill` followed by `words`, `letters`, `english`, `german`, `swedish` or `alphabet:` and some characters after `gen` to choose how the rest of the grid is filled.\n* Put `--all-occurrences` after `solve` to find words that occur more than once.\n* Put `--ignore-case` after `solve` to let uppercase and lowercase letters match each other, or `--case-sensitive` to keep them apart.\n  These options take precedence over the crossword's headers.\n  Put `--normalization` followed by `nfc` or `nfkc` after `solve` to normalize the grid and the words before matching, or `none` not to.\n  Put `--fold` followed by `diacritics` or a list of equivalent characters such as `ä=a,ö=o` after `solve` to let those characters match, or `none` not to.\n* Put `--message` followed by a message after `gen` to hide it in the cells not part of any word.\n  Put `--message` after `solve` to show the characters left over after solving, which spell the hidden message.\n* Put `--words` followed by the path to a file with words after `gen` to use those words too. Use `-` to read them from stdin.\n* Put `--output` followed by a path after `gen` or `solve` to write the output to that file.\n* Put `--format` followed by `text`, `html`, `svg`, `json` or `ipuz` after `gen` or `solve` to choose the output format. JSON and ipuz need the `json` feature.\n  Put `--answer-key` after `gen` or `solve` to show the solution in HTML or SVG output.\n  Put `--font` followed by a font family or `--cell-size` followed by a number of pixels after `gen` or `solve` to change how SVG output looks.\n* Put `--color` followed by `auto`, `always` or `never` after `gen` or `solve` to choose whether to use colors.\n  By default, colors are only used if the output is a terminal and `NO_COLOR` is not set.\n* Use `book` followed by `--font-file` and a path to a font and then paths to crosswords or directories of crosswords to write a PDF with a page for each crossword.\n  Put `--answer-key` after `book` to add pages with the solutions at the end. This needs the `pdf` feature.\n* Options go before the words or file names.\nExample: `crustword watch solve crosswords/generated`")
     }
 }
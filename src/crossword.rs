@@ -1,29 +1,100 @@
+mod direction;
+mod fill;
 mod fmt;
 mod gen;
+mod grapheme;
+mod html;
+#[cfg(feature = "json")]
+mod ipuz;
+#[cfg(feature = "json")]
+mod json;
+mod matching;
 mod parse;
+#[cfg(feature = "pdf")]
+mod pdf;
 mod solver;
+mod svg;
 
-use crate::{args::ArgWords, util::Point, Error};
+pub use direction::Direction;
+pub use fill::{Fill, ENGLISH, GERMAN, SWEDISH};
+pub use gen::{GenError, GenOptions, Generated};
+pub use matching::{Folding, Matching, Normalization};
+pub use parse::{split_directions, split_words, ParseError};
+#[cfg(feature = "pdf")]
+pub use pdf::{write_book, PdfError};
+pub use svg::SvgOptions;
+
+use crate::{args::ArgWords, util::Point};
+use grapheme::{graphemes, is_separator, Grapheme};
 use std::{
     borrow::Cow,
-    cmp, io,
+    io,
     ops::{Index, IndexMut},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Where and in which direction a word was found in the grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// The cell containing the first character of the word.
+    pub start: Point,
+    /// The direction the word is read in.
+    pub direction: Direction,
+    /// All cells covered by the word, in reading order.
+    pub points: Vec<Point>,
+}
+
+impl Placement {
+    /// The cell containing the last character of the word.
+    pub fn end(&self) -> Point {
+        *self.points.last().unwrap_or(&self.start)
+    }
+
+    /// Whether this placement covers exactly the given cells, in either reading order.
+    ///
+    /// This is the case for palindromes, which read the same in opposite directions.
+    fn covers(&self, points: &[Point]) -> bool {
+        self.points == points || self.points.iter().rev().eq(points)
+    }
+}
+
+/// The result of solving a crossword.
+#[derive(Debug, PartialEq)]
+pub struct Solution {
+    /// All words in the order of the word list.
+    pub words: Vec<SolvedWord>,
+}
+
+/// A word of the word list and everywhere it was found.
+#[derive(Debug, PartialEq)]
+pub struct SolvedWord {
+    pub word: String,
+    /// Empty if the word was not found.
+    ///
+    /// More than one placement means the word occurs more than once and the crossword is ambiguous.
+    pub placements: Vec<Placement>,
+}
+
+/// The darkest shade of the grayscale ramp of the 256-color palette, which ends at 255.
+const DARKEST_GRAY: u8 = 232;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cell {
-    char: char,
+    grapheme: Grapheme,
     highlighting: u8,
 }
 
 impl Cell {
-    fn new(char: char) -> Self {
+    fn new(grapheme: impl Into<Grapheme>) -> Self {
+        let grapheme = grapheme.into();
         let highlighting = 255;
 
-        Self { char, highlighting }
+        Self {
+            grapheme,
+            highlighting,
+        }
     }
 
-    fn highlighted(self) -> bool {
+    fn highlighted(&self) -> bool {
         self.highlighting != 255
     }
 }
@@ -31,54 +102,163 @@ impl Cell {
 /// A word as part of a crossword.
 #[derive(Debug, PartialEq)]
 pub struct Word<'a> {
+    /// The word as it is shown in the word list, which may be a phrase such as `ICE CREAM` or `T-SHIRT`.
     str: Cow<'a, str>,
-    /// [`Self::str`]'s length in Unicode characters.
+    /// The word as it is written in the grid, which is [`Self::str`] without separators such as `ICECREAM`.
+    grid_str: Cow<'a, str>,
+    /// [`Self::grid_str`]'s length in graphemes, which is the number of cells it takes up.
     ///
-    /// This is far more accurate and language-independent than [`str::len`].
+    /// This is far more accurate and language-independent than [`str::len`] or counting `char`s.
     len: usize,
     /// This property can have different meanings depending on the context.
     ///
     /// * In a solving context, this means the word has been found.
     /// * In a generation context, this means the word has been included in the grid.
     marked: bool,
+    /// Where this word was found by the solver.
+    placements: Vec<Placement>,
 }
 
 impl<'a> Word<'a> {
     pub fn new(str: Cow<'a, str>) -> Self {
-        let len = str.chars().count();
+        let grid_str = if graphemes(&str).any(is_separator) {
+            Cow::Owned(
+                graphemes(&str)
+                    .filter(|&grapheme| !is_separator(grapheme))
+                    .collect(),
+            )
+        } else {
+            str.clone()
+        };
+        let len = graphemes(&grid_str).count();
         let marked = false;
+        let placements = Vec::new();
+
+        Self {
+            str,
+            grid_str,
+            len,
+            marked,
+            placements,
+        }
+    }
+
+    /// The graphemes written in the grid.
+    fn graphemes(&self) -> impl DoubleEndedIterator<Item = &str> + '_ {
+        graphemes(&self.grid_str)
+    }
 
-        Self { str, len, marked }
+    /// Whether the word has nothing to write in the grid, such as a lone hyphen.
+    fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
-    fn chars(&self) -> impl Iterator<Item = char> + '_ + DoubleEndedIterator {
-        self.str.chars()
+    /// The word as it is written in the text form of a word list, with phrases in double quotes.
+    fn quoted(&self) -> Cow<'_, str> {
+        if self.str.contains(char::is_whitespace) {
+            Cow::Owned(format!("\"{}\"", self.str))
+        } else {
+            Cow::Borrowed(&self.str)
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Crossword<'a> {
+    /// Metadata such as the title and author as key-value pairs in the order they were given.
+    headers: Vec<(Cow<'a, str>, Cow<'a, str>)>,
     cells: Vec<Cell>,
     width: usize,
     height: usize,
     words: Vec<Word<'a>>,
     solved: bool,
+    /// How the words are compared with the grid, which can be set with the `case` and `normalization` headers.
+    matching: Matching,
+    /// The directions the words are written in, which can be limited with the `directions` header.
+    directions: Vec<Direction>,
 }
 
 impl Crossword<'_> {
+    /// The value of the `title` header, if there is one.
+    pub fn title(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == "title")
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// How the words are compared with the grid.
+    pub fn matching(&self) -> &Matching {
+        &self.matching
+    }
+
+    pub fn set_matching(&mut self, matching: Matching) {
+        self.matching = matching;
+    }
+
+    /// The directions the words are written in.
+    pub fn directions(&self) -> &[Direction] {
+        &self.directions
+    }
+
+    /// Copies everything the crossword borrows so that it no longer depends on the text it was parsed from.
+    fn into_owned(self) -> Crossword<'static> {
+        let owned = |str: Cow<str>| Cow::Owned(str.into_owned());
+        Crossword {
+            headers: self
+                .headers
+                .into_iter()
+                .map(|(key, value)| (owned(key), owned(value)))
+                .collect(),
+            cells: self.cells,
+            width: self.width,
+            height: self.height,
+            words: self
+                .words
+                .into_iter()
+                .map(|word| Word {
+                    str: owned(word.str),
+                    grid_str: owned(word.grid_str),
+                    ..word
+                })
+                .collect(),
+            solved: self.solved,
+            matching: self.matching,
+            directions: self.directions,
+        }
+    }
+
+    /// The graphemes of the cells not part of any found word in reading order.
+    ///
+    /// After solving a hidden message puzzle, these spell the message.
+    pub fn leftover(&self) -> String {
+        self.cells
+            .iter()
+            .filter(|cell| !cell.highlighted())
+            .map(|cell| cell.grapheme.as_str())
+            .collect()
+    }
+
+    /// Makes the cell a shade darker for every word going through it,
+    /// stopping at the darkest gray so that any number of words can share a cell.
     fn highlight(&mut self, point: Point) {
-        self[point].highlighting -= 2;
+        let cell = &mut self[point];
+        cell.highlighting = cell.highlighting.saturating_sub(2).max(DARKEST_GRAY);
     }
 
-    /// Gets an index to index [`cells`] and makes clamps the index if required to make sure the index is never out of bounds.
     fn get_index(&self, point: Point) -> usize {
-        let point = Point {
-            x: cmp::min(self.width - 1, point.x),
-            y: cmp::min(self.height - 1, point.y),
-        };
         point.x + self.width * point.y
     }
 
+    /// Gets the cell at this point or `None` if the point is outside of the grid.
+    fn get(&self, point: Point) -> Option<&Cell> {
+        if point.x >= self.width || point.y >= self.height {
+            None
+        } else {
+            self.cells.get(self.get_index(point))
+        }
+    }
+
     fn rows(&self) -> impl Iterator<Item = &[Cell]> {
         self.cells.chunks_exact(self.width)
     }
@@ -99,13 +279,21 @@ impl IndexMut<Point> for Crossword<'_> {
     }
 }
 
-pub fn gen(watch: bool, arg_words: ArgWords, writer: &mut impl io::Write) -> Result<(), Error> {
+/// Generates a crossword out of the given words in which every word occurs exactly once.
+pub fn gen(
+    watch: bool,
+    arg_words: ArgWords,
+    options: &GenOptions,
+    writer: &mut impl io::Write,
+) -> Result<Generated, GenError> {
     let mut words = Vec::<Word>::with_capacity(arg_words.len());
 
     for arg_word in arg_words {
         let word = Word::new(arg_word.into());
-        words.push(word);
+        if !word.is_empty() {
+            words.push(word);
+        }
     }
 
-    gen::gen(watch, &words, writer)
+    gen::gen(watch, &words, options, writer)
 }
@@ -0,0 +1,110 @@
+use crate::util::Point;
+
+/// One of the eight directions a word can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Direction {
+    North,
+    Northeast,
+    East,
+    Southeast,
+    South,
+    Southwest,
+    West,
+    Northwest,
+}
+
+impl Direction {
+    /// All eight directions, clockwise starting from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::Northeast,
+        Direction::East,
+        Direction::Southeast,
+        Direction::South,
+        Direction::Southwest,
+        Direction::West,
+        Direction::Northwest,
+    ];
+
+    /// The change in x and y when taking one step in this direction.
+    pub fn step(self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::Northeast => (1, -1),
+            Direction::East => (1, 0),
+            Direction::Southeast => (1, 1),
+            Direction::South => (0, 1),
+            Direction::Southwest => (-1, 1),
+            Direction::West => (-1, 0),
+            Direction::Northwest => (-1, -1),
+        }
+    }
+
+    /// The direction pointing the other way.
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::Northeast => Direction::Southwest,
+            Direction::East => Direction::West,
+            Direction::Southeast => Direction::Northwest,
+            Direction::South => Direction::North,
+            Direction::Southwest => Direction::Northeast,
+            Direction::West => Direction::East,
+            Direction::Northwest => Direction::Southeast,
+        }
+    }
+
+    /// Returns the point `n` steps away from `start` in this direction.
+    ///
+    /// Returns `None` if the point is to the left of or above the grid.
+    pub fn nth_point(self, start: Point, n: usize) -> Option<Point> {
+        let (x_step, y_step) = self.step();
+
+        Some(Point {
+            x: start.x.checked_add_signed(x_step * n as isize)?,
+            y: start.y.checked_add_signed(y_step * n as isize)?,
+        })
+    }
+
+    /// Returns `len` points walking from `start` in this direction.
+    ///
+    /// Returns `None` if the walk leaves the grid to the left or to the top.
+    /// The right and bottom edges depend on the grid and have to be checked by the caller.
+    pub fn points(self, start: Point, len: usize) -> Option<Vec<Point>> {
+        (0..len).map(|n| self.nth_point(start, n)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let start = Point { x: 1, y: 2 };
+
+        assert_eq!(
+            Direction::Northeast.points(start, 3),
+            Some(vec![
+                Point { x: 1, y: 2 },
+                Point { x: 2, y: 1 },
+                Point { x: 3, y: 0 }
+            ])
+        );
+        assert_eq!(
+            Direction::West.points(start, 2),
+            Some(vec![Point { x: 1, y: 2 }, Point { x: 0, y: 2 }])
+        );
+        assert_eq!(Direction::West.points(start, 3), None);
+        assert_eq!(Direction::North.points(start, 4), None);
+        assert_eq!(Direction::South.points(start, 0), Some(vec![]));
+        assert!(Direction::ALL
+            .iter()
+            .all(|&direction| direction.opposite().opposite() == direction));
+    }
+}
@@ -0,0 +1,243 @@
+use super::{GenError, Grapheme, Word};
+use rand::{
+    distributions::{Bernoulli, Uniform, WeightedIndex},
+    prelude::Distribution,
+    Rng,
+};
+
+/// Letter frequencies in English text, in tenths of a percent.
+pub const ENGLISH: &[(char, u32)] = &[
+    ('a', 82),
+    ('b', 15),
+    ('c', 28),
+    ('d', 43),
+    ('e', 127),
+    ('f', 22),
+    ('g', 20),
+    ('h', 61),
+    ('i', 70),
+    ('j', 2),
+    ('k', 8),
+    ('l', 40),
+    ('m', 24),
+    ('n', 67),
+    ('o', 75),
+    ('p', 19),
+    ('q', 1),
+    ('r', 60),
+    ('s', 63),
+    ('t', 91),
+    ('u', 28),
+    ('v', 10),
+    ('w', 24),
+    ('x', 2),
+    ('y', 20),
+    ('z', 1),
+];
+
+/// Letter frequencies in German text, in tenths of a percent.
+pub const GERMAN: &[(char, u32)] = &[
+    ('a', 65),
+    ('b', 19),
+    ('c', 27),
+    ('d', 51),
+    ('e', 174),
+    ('f', 17),
+    ('g', 30),
+    ('h', 48),
+    ('i', 76),
+    ('j', 3),
+    ('k', 12),
+    ('l', 34),
+    ('m', 25),
+    ('n', 98),
+    ('o', 25),
+    ('p', 8),
+    ('q', 1),
+    ('r', 70),
+    ('s', 73),
+    ('t', 62),
+    ('u', 44),
+    ('v', 7),
+    ('w', 19),
+    ('x', 1),
+    ('y', 1),
+    ('z', 11),
+    ('ä', 6),
+    ('ö', 3),
+    ('ü', 7),
+    ('ß', 3),
+];
+
+/// Letter frequencies in Swedish text, in tenths of a percent.
+pub const SWEDISH: &[(char, u32)] = &[
+    ('a', 93),
+    ('b', 13),
+    ('c', 15),
+    ('d', 45),
+    ('e', 101),
+    ('f', 20),
+    ('g', 33),
+    ('h', 21),
+    ('i', 58),
+    ('j', 6),
+    ('k', 32),
+    ('l', 53),
+    ('m', 35),
+    ('n', 88),
+    ('o', 41),
+    ('p', 18),
+    ('r', 84),
+    ('s', 66),
+    ('t', 77),
+    ('u', 19),
+    ('v', 24),
+    ('w', 1),
+    ('x', 2),
+    ('y', 7),
+    ('z', 1),
+    ('å', 13),
+    ('ä', 18),
+    ('ö', 13),
+];
+
+/// How the cells that are not part of any word are filled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fill {
+    /// Cycle through the characters of the words in the grid, skipping some at random.
+    Words,
+    /// Choose uniformly from the characters of the words.
+    Letters,
+    /// Choose uniformly from the given characters.
+    Alphabet(Vec<Grapheme>),
+    /// Choose from the given characters weighted by how common they are, such as [`ENGLISH`].
+    ///
+    /// If all words are written in uppercase, the characters are uppercased too.
+    Frequencies(&'static [(char, u32)]),
+}
+
+/// Produces fill characters according to a [`Fill`].
+pub enum Filler {
+    Cycle {
+        graphemes: Vec<Grapheme>,
+        next: usize,
+        skip: Bernoulli,
+    },
+    Uniform(Vec<Grapheme>, Uniform<usize>),
+    Weighted(Vec<Grapheme>, WeightedIndex<u32>),
+}
+
+impl Filler {
+    /// `word_graphemes` are the characters of the words in the grid in reading order.
+    pub fn new(
+        fill: &Fill,
+        words: &[Word],
+        word_graphemes: Vec<Grapheme>,
+    ) -> Result<Self, GenError> {
+        match fill {
+            Fill::Words => {
+                if word_graphemes.is_empty() {
+                    return Err(GenError::InvalidFill);
+                }
+                Ok(Filler::Cycle {
+                    graphemes: word_graphemes,
+                    next: 0,
+                    skip: Bernoulli::new(0.5).unwrap(),
+                })
+            }
+            Fill::Letters => {
+                let mut graphemes = word_graphemes;
+                graphemes.sort_unstable();
+                graphemes.dedup();
+                Self::uniform(graphemes)
+            }
+            Fill::Alphabet(graphemes) => Self::uniform(graphemes.clone()),
+            Fill::Frequencies(frequencies) => {
+                let uppercase = words
+                    .iter()
+                    .flat_map(|word| word.str.chars())
+                    .filter(|char| char.is_lowercase() || char.is_uppercase())
+                    .all(char::is_uppercase);
+                let graphemes = frequencies
+                    .iter()
+                    .map(|&(char, _)| {
+                        if uppercase {
+                            // Only use the first character so that for example 'ß' stays one character
+                            char.to_uppercase().next().unwrap_or(char).into()
+                        } else {
+                            char.into()
+                        }
+                    })
+                    .collect();
+                let weights = WeightedIndex::new(frequencies.iter().map(|&(_, weight)| weight))
+                    .map_err(|_| GenError::InvalidFill)?;
+                Ok(Filler::Weighted(graphemes, weights))
+            }
+        }
+    }
+
+    fn uniform(graphemes: Vec<Grapheme>) -> Result<Self, GenError> {
+        if graphemes.is_empty() {
+            Err(GenError::InvalidFill)
+        } else {
+            let range = Uniform::from(0..graphemes.len());
+            Ok(Filler::Uniform(graphemes, range))
+        }
+    }
+
+    pub fn next(&mut self, rng: &mut impl Rng) -> Grapheme {
+        match self {
+            Filler::Cycle {
+                graphemes,
+                next,
+                skip,
+            } => {
+                if skip.sample(rng) {
+                    *next += 1;
+                }
+                if *next >= graphemes.len() {
+                    // Start over
+                    *next = 0;
+                }
+                let grapheme = graphemes[*next].clone();
+                *next += 1;
+                grapheme
+            }
+            Filler::Uniform(graphemes, range) => graphemes[range.sample(rng)].clone(),
+            Filler::Weighted(graphemes, weights) => graphemes[weights.sample(rng)].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_filler() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let words = [Word::new("ABBA".into())];
+
+        let word_graphemes = "ABBA".chars().map(Grapheme::from).collect();
+        let mut filler = Filler::new(&Fill::Letters, &words, word_graphemes).unwrap();
+        assert!((0..100)
+            .map(|_| filler.next(&mut rng))
+            .all(|grapheme| grapheme.as_str() == "A" || grapheme.as_str() == "B"));
+
+        let mut filler = Filler::new(&Fill::Frequencies(SWEDISH), &words, Vec::new()).unwrap();
+        assert!((0..100)
+            .map(|_| filler.next(&mut rng))
+            .all(|grapheme| grapheme.as_str().chars().all(char::is_uppercase)));
+
+        let alphabet = Fill::Alphabet(vec!["x\u{301}".into()]);
+        let mut filler = Filler::new(&alphabet, &words, Vec::new()).unwrap();
+        assert_eq!(filler.next(&mut rng).as_str(), "x\u{301}");
+
+        assert!(matches!(
+            Filler::new(&Fill::Alphabet(Vec::new()), &words, Vec::new()),
+            Err(GenError::InvalidFill)
+        ));
+    }
+}
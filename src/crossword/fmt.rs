@@ -1,20 +1,19 @@
-use super::Crossword;
+use super::{Crossword, Direction, GenError, ParseError, Solution};
 use crate::util::escape_sequences;
 use std::fmt;
-use unicode_width::UnicodeWidthChar;
 
-impl fmt::Display for Crossword<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}×{}", self.width, self.height)?;
+impl Crossword<'_> {
+    /// Writes the grid with the found words highlighted in shades of gray.
+    fn fmt_grid_colored(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", escape_sequences::WHITE_ON_DEFAULT)?;
         for row in self.rows() {
             let mut row = row.iter().peekable();
             while let Some(cell) = row.next() {
                 if cell.highlighted() {
                     escape_sequences::write_grayscale(f, cell.highlighting)?;
-                    write!(f, "{}", cell.char)?;
+                    write!(f, "{}", cell.grapheme)?;
 
-                    if cell.char.width() == Some(1) {
+                    if cell.grapheme.width() == 1 {
                         if let Some(next_cell) = row.peek() {
                             if next_cell.highlighted() {
                                 write!(f, " ")?;
@@ -26,21 +25,70 @@ impl fmt::Display for Crossword<'_> {
                     } else {
                         write!(f, "{}", escape_sequences::WHITE_ON_DEFAULT)?;
                     }
-                } else if cell.char.width() > Some(1) || row.peek().is_none() {
-                    write!(f, "{}", cell.char)?;
+                } else if cell.grapheme.width() > 1 || row.peek().is_none() {
+                    write!(f, "{}", cell.grapheme)?;
                 } else {
-                    write!(f, "{} ", cell.char)?;
+                    write!(f, "{} ", cell.grapheme)?;
                 }
             }
             writeln!(f)?;
         }
-        write!(f, "{}", escape_sequences::RESET)?;
+        write!(f, "{}", escape_sequences::RESET)
+    }
+
+    /// Writes the grid with brackets around runs of highlighted cells instead of colors.
+    ///
+    /// The brackets take the place of the spaces between cells so that the columns stay aligned.
+    fn fmt_grid_plain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let any_highlighted = self.cells.iter().any(|cell| cell.highlighted());
+
+        for row in self.rows() {
+            let mut previous_highlighted = false;
+            for (i, cell) in row.iter().enumerate() {
+                let highlighted = cell.highlighted();
+                let separator = match (previous_highlighted, highlighted) {
+                    (false, true) => "[",
+                    (true, false) => "]",
+                    // Only make room for brackets at the start of the row if they are needed
+                    _ if i == 0 && !any_highlighted => "",
+                    // Full-width characters already take up the room of a space
+                    _ if i != 0 && row[i - 1].grapheme.width() > 1 && !any_highlighted => "",
+                    _ => " ",
+                };
+                write!(f, "{}{}", separator, cell.grapheme)?;
+                previous_highlighted = highlighted;
+            }
+            if previous_highlighted {
+                write!(f, "]")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Crossword<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.headers {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        writeln!(f, "{}×{}", self.width, self.height)?;
+        if escape_sequences::enabled() {
+            self.fmt_grid_colored(f)?;
+        } else {
+            self.fmt_grid_plain(f)?;
+        }
 
         let mut words = self.words.iter().peekable();
         let mut found_word_count = 0;
         while let Some(word) = words.next() {
             if word.marked {
-                write!(f, "{}{}", escape_sequences::DARK_GRAY_FOREGROUND, word.str)?;
+                if escape_sequences::enabled() {
+                    write!(f, "{}{}", escape_sequences::DARK_GRAY_FOREGROUND, word.str)?;
+                } else {
+                    write!(f, "[{}]", word.str)?;
+                }
                 found_word_count += 1;
             } else {
                 write!(f, "{}", word.str)?;
@@ -49,15 +97,32 @@ impl fmt::Display for Crossword<'_> {
                 write!(f, ", ")?;
             }
             if word.marked {
-                write!(f, "{}", escape_sequences::RESET)?;
+                write!(f, "{}", escape_sequences::color(escape_sequences::RESET))?;
             }
         }
         writeln!(f)?;
 
+        for word in &self.words {
+            if word.placements.len() > 1 {
+                writeln!(
+                    f,
+                    "{}{} was found {} times!{}",
+                    escape_sequences::color(escape_sequences::YELLOW_FOREGROUND),
+                    word.str,
+                    word.placements.len(),
+                    escape_sequences::color(escape_sequences::RESET)
+                )?;
+            }
+        }
+
         if self.solved {
             let not_found_word_count = self.words.len() - found_word_count;
             if not_found_word_count != 0 {
-                write!(f, "{}", escape_sequences::RED_FOREGROUND)?;
+                write!(
+                    f,
+                    "{}",
+                    escape_sequences::color(escape_sequences::RED_FOREGROUND)
+                )?;
                 if not_found_word_count == 1 {
                     writeln!(f, "1 word not found!")?;
                 } else if not_found_word_count == self.words.len() {
@@ -66,18 +131,123 @@ impl fmt::Display for Crossword<'_> {
                     writeln!(f, "{} words not found!", not_found_word_count)?;
                 }
             } else {
-                writeln!(f, "{}All words found!", escape_sequences::GREEN_FOREGROUND)?;
+                writeln!(
+                    f,
+                    "{}All words found!",
+                    escape_sequences::color(escape_sequences::GREEN_FOREGROUND)
+                )?;
             }
         } else {
-            writeln!(f, "{}Unsolved.", escape_sequences::YELLOW_FOREGROUND)?;
+            writeln!(
+                f,
+                "{}Unsolved.",
+                escape_sequences::color(escape_sequences::YELLOW_FOREGROUND)
+            )?;
         }
-        write!(f, "{}", escape_sequences::RESET)?;
+        write!(f, "{}", escape_sequences::color(escape_sequences::RESET))?;
         writeln!(f)?;
 
         Ok(())
     }
 }
 
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::North => "north",
+            Direction::Northeast => "northeast",
+            Direction::East => "east",
+            Direction::Southeast => "southeast",
+            Direction::South => "south",
+            Direction::Southwest => "southwest",
+            Direction::West => "west",
+            Direction::Northwest => "northwest",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for solved_word in &self.words {
+            if solved_word.placements.is_empty() {
+                writeln!(f, "{}: not found", solved_word.word)?;
+            }
+            for placement in &solved_word.placements {
+                writeln!(
+                    f,
+                    "{}: {} to {} going {}",
+                    solved_word.word,
+                    placement.start,
+                    placement.end(),
+                    placement.direction
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenError::NoWords => write!(f, "no words"),
+            GenError::NoDirections => write!(f, "no directions"),
+            GenError::InvalidOverlap => write!(f, "invalid overlap"),
+            GenError::InvalidSize => write!(f, "invalid size"),
+            GenError::InvalidFill => write!(f, "invalid fill"),
+            GenError::NoRoom(word) => write!(f, "not enough room for the word \"{}\"", word),
+            GenError::NotUnique(word) => {
+                write!(f, "could not make the word \"{}\" occur only once", word)
+            }
+            GenError::MessageDoesNotFit => {
+                write!(f, "could not leave exactly enough room for the message")
+            }
+            GenError::WritingFailed => write!(f, "writing failed"),
+        }
+    }
+}
+
+#[cfg(feature = "pdf")]
+impl fmt::Display for super::PdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            super::PdfError::InvalidFont => write!(f, "invalid font file"),
+            super::PdfError::MissingGlyph(char) => {
+                write!(f, "the font has no glyph for the character '{}'", char)
+            }
+            super::PdfError::UnsupportedFont => {
+                write!(f, "fonts with CFF2 outlines can't be embedded")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::EmptyGrid => write!(f, "empty grid"),
+            ParseError::NoWords { .. } => write!(f, "no words after the separator"),
+            ParseError::AmbiguousWordList { .. } => write!(
+                f,
+                "the lines after this blank line could be rows or the word list, put `---` before the word list"
+            ),
+            ParseError::InconsistentWidth {
+                expected, found, ..
+            } => write!(
+                f,
+                "inconsistent width: expected {} characters like the first row but found {}",
+                expected, found
+            ),
+            ParseError::UnclosedQuote { .. } => write!(f, "unclosed quote in the word list"),
+            ParseError::InvalidHeader { key, .. } => write!(f, "invalid value for `{}`", key),
+            #[cfg(feature = "json")]
+            ParseError::Json { message, .. } => write!(f, "invalid JSON: {}", message),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,27 +269,108 @@ mod tests {
         })
         .unwrap();
 
-        //assert_eq!(
-        //    format!("{}", crossword),
-        //    formatdoc!(
-        //        "18×9\n\
-        //         {}a a a a a a a a a a a a a a a a a a
-        //         a a a a a a i a a a a a a a a a a a
-        //         a a a a a a s a a a a t h e a a a a
-        //         a f o r m a t t e d a a a a a a a o
-        //         a a a a a a a a a a a a a a a a a u
-        //         a a a a a a a a a a a a a a a a a t
-        //         a a a a a a a a a a a a a a a a a p
-        //         a a a a a a a a a a a a a a a a a u
-        //         a a a a a a a a a a a a a a t e s t
-        //         {}this, is, the, formatted, output, test
-        //         {}Unsolved.
-        //         {}",
-        //        escape_sequences::WHITE_ON_DEFAULT,
-        //        escape_sequences::RESET,
-        //        escape_sequences::YELLOW_FOREGROUND,
-        //        escape_sequences::RESET
-        //    )
-        //);
+        assert_eq!(
+            format!("{}", crossword),
+            formatdoc!(
+                "18×9\n\
+                 {}a a a a a a a a a a a a a a a a a a
+                 a a a a a a i a a a a a a a a a a a
+                 a a a a a a s a a a a t h e a a a a
+                 a f o r m a t t e d a a a a a a a o
+                 a a a a a a a a a a a a a a a a a u
+                 a a a a a a a a a a a a a a a a a t
+                 a a a a a a a a a a a a a a a a a p
+                 a a a a a a a a a a a a a a a a a u
+                 a a a a a a a a a a a a a a t e s t
+                 {}this, is, the, formatted, output, test
+                 {}Unsolved.
+                 {}
+                 ",
+                escape_sequences::WHITE_ON_DEFAULT,
+                escape_sequences::RESET,
+                escape_sequences::YELLOW_FOREGROUND,
+                escape_sequences::RESET
+            )
+        );
+    }
+
+    #[test]
+    fn test_fmt_duplicates() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "treeaa
+             aaaaaa
+             aaeert
+             tree"
+        })
+        .unwrap();
+
+        crossword.solve(false, &Direction::ALL, true);
+
+        assert!(format!("{}", crossword).contains(&format!(
+            "{}tree was found 2 times!{}",
+            escape_sequences::YELLOW_FOREGROUND,
+            escape_sequences::RESET
+        )));
+    }
+
+    #[test]
+    fn test_fmt_plain() {
+        escape_sequences::set_enabled(false);
+        let mut crossword = Crossword::try_from(indoc! {
+            "redxx
+             xaxxx
+             xxtxx
+             red rat cat"
+        })
+        .unwrap();
+
+        assert_eq!(
+            format!("{}", crossword),
+            indoc! {
+                "5×3
+                 r e d x x
+                 x a x x x
+                 x x t x x
+                 red, rat, cat
+                 Unsolved.
+
+                 "
+            }
+        );
+
+        crossword.solve(false, &Direction::ALL, false);
+
+        assert_eq!(
+            format!("{}", crossword),
+            indoc! {
+                "5×3
+                 [r e d]x x
+                  x[a]x x x
+                  x x[t]x x
+                 [red], [rat], cat
+                 1 word not found!
+
+                 "
+            }
+        );
+        escape_sequences::set_enabled(true);
+    }
+
+    #[test]
+    fn test_fmt_headers() {
+        let crossword = Crossword::try_from(indoc! {
+            "title: Greetings
+             language: English
+             hi
+             hi"
+        })
+        .unwrap();
+
+        assert!(format!("{}", crossword).starts_with(indoc! {
+            "title: Greetings
+             language: English
+             2×1
+             "
+        }));
     }
 }
@@ -1,58 +1,98 @@
-use super::Word;
-use crate::{
-    util::{escape_sequences, Point},
-    Error,
-};
-use rand::{
-    distributions::{Bernoulli, Uniform},
-    prelude::Distribution,
-    rngs::SmallRng,
-    Rng, SeedableRng,
+use super::{
+    fill::{Fill, Filler},
+    grapheme::{self, Grapheme},
+    Cell, Crossword, Direction, Matching, Word,
 };
+use crate::util::{escape_sequences, Point};
+use rand::{distributions::Bernoulli, prelude::Distribution, seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::{
     io,
-    ops::{ControlFlow, Index, IndexMut},
+    ops::{Index, IndexMut},
     thread, time,
 };
-use unicode_width::UnicodeWidthChar;
+
+/// How many placements are tried on one grid before making the grid bigger.
+const MAX_ATTEMPTS: usize = 1000;
+/// How many times the grid is made bigger before giving up.
+const MAX_GROWTHS: usize = 10;
+/// How many fill characters are tried for one cell to avoid creating another occurrence of a word.
+const MAX_FILL_ATTEMPTS: usize = 100;
+/// How many times the grid is filled again if a word occurs more than once before placing the words again.
+const MAX_REROLLS: usize = 10;
+/// How many times the words are placed again if a word still occurs more than once.
+const MAX_PLACEMENTS: usize = 10;
+/// How many times the words are placed again, possibly on a grid of another size, to leave exactly as many cells
+/// free as the hidden message has characters.
+const MAX_MESSAGE_ATTEMPTS: usize = 100;
+
+/// An error that occurred while generating a crossword.
+#[derive(Debug, PartialEq)]
+pub enum GenError {
+    NoWords,
+    NoDirections,
+    InvalidOverlap,
+    InvalidSize,
+    InvalidFill,
+    /// This word could not be placed in the grid, not even after backtracking and making the grid bigger.
+    NoRoom(String),
+    /// This word always occurred more than once, no matter how the grid was filled.
+    NotUnique(String),
+    /// The number of cells left over after placing the words never matched the length of the hidden message.
+    MessageDoesNotFit,
+    WritingFailed,
+}
+
+/// Where a word starts and in which direction it goes.
+type Position = (Point, Direction);
 
 #[derive(Debug)]
 struct Grid {
-    cells: Vec<char>,
+    /// Empty cells hold the empty grapheme.
+    cells: Vec<Grapheme>,
     width: usize,
     height: usize,
-    width_range: Uniform<usize>,
-    height_range: Uniform<usize>,
 }
 
 impl Grid {
-    fn new(words: &[Word], rng: &mut impl Rng) -> Result<Self, Error> {
-        if let Some(max_word_len) = words.iter().map(|word| word.len).max() {
-            let (width, height) = (
-                rng.gen_range(max_word_len..max_word_len * 2),
-                rng.gen_range(max_word_len..max_word_len * 2),
-            );
-            let cells = vec![char::default(); width * height];
-
-            let width_range = Uniform::from(0..width);
-            let height_range = Uniform::from(0..height);
+    /// Creates an empty grid of the size given in the options.
+    ///
+    /// Dimensions not given are chosen randomly depending on the length of the longest word.
+    fn new(
+        words: &[Word],
+        message: Option<&Word>,
+        options: &GenOptions,
+        rng: &mut impl Rng,
+    ) -> Result<Self, GenError> {
+        if options.width == Some(0) || options.height == Some(0) {
+            return Err(GenError::InvalidSize);
+        }
 
-            Ok(Self {
-                cells,
-                width,
-                height,
-                width_range,
-                height_range,
-            })
+        if let Some(max_word_len) = words.iter().map(|word| word.len).max() {
+            let mut random_len = || rng.gen_range(max_word_len..max_word_len * 2);
+            let width = options.width.unwrap_or_else(&mut random_len);
+            let height = match (options.height, message) {
+                (Some(height), _) => height,
+                // Leave about as many cells free as the message needs, crossing words free up the rest
+                (None, Some(message)) => {
+                    let cells = words.iter().map(|word| word.len).sum::<usize>() + message.len;
+                    (cells / width).max(1)
+                }
+                (None, None) => random_len(),
+            };
+            Ok(Self::empty(width, height))
         } else {
-            Err("no words")
+            Err(GenError::NoWords)
         }
     }
 
-    fn get_rand_point(&self, rng: &mut impl Rng) -> Point {
-        Point {
-            x: self.width_range.sample(rng),
-            y: self.height_range.sample(rng),
+    fn empty(width: usize, height: usize) -> Self {
+        let cells = vec![Grapheme::default(); width * height];
+
+        Self {
+            cells,
+            width,
+            height,
         }
     }
 
@@ -60,17 +100,165 @@ impl Grid {
         point.x + self.width * point.y
     }
 
-    fn get(&self, point: Point) -> Option<&char> {
+    fn get(&self, point: Point) -> Option<&Grapheme> {
         if point.x >= self.width || point.y >= self.height {
             None
         } else {
             self.cells.get(self.get_index(point))
         }
     }
+
+    /// Returns how many characters of the word would be placed on the same characters already in the grid,
+    /// or `None` if the word does not fit at this position.
+    ///
+    /// `placed` holds the cells of the words that were already placed.
+    fn overlap(
+        &self,
+        start: Point,
+        direction: Direction,
+        word: &Word,
+        placed: &[&[Point]],
+    ) -> Option<usize> {
+        let points = direction.points(start, word.len)?;
+        let mut overlap = 0;
+        for (&point, grapheme) in points.iter().zip(word.graphemes()) {
+            match self.get(point) {
+                Some(existing) if existing.is_empty() => {}
+                Some(existing) if existing.as_str() == grapheme => overlap += 1,
+                _ => return None,
+            }
+        }
+
+        // A word entirely on top of other characters would be contained in another word,
+        // and a word entirely covering another word would contain it
+        if overlap == word.len
+            || placed
+                .iter()
+                .any(|cells| cells.iter().all(|cell| points.contains(cell)))
+        {
+            None
+        } else {
+            Some(overlap)
+        }
+    }
+
+    /// Returns all positions the word fits at,
+    /// split into the ones crossing other words and the ones not crossing other words.
+    fn placements(
+        &self,
+        word: &Word,
+        directions: &[Direction],
+        placed: &[&[Point]],
+    ) -> (Vec<Position>, Vec<Position>) {
+        let mut crossing = Vec::new();
+        let mut not_crossing = Vec::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let start = Point { x, y };
+                for &direction in directions {
+                    match self.overlap(start, direction, word, placed) {
+                        Some(0) => not_crossing.push((start, direction)),
+                        Some(_) => crossing.push((start, direction)),
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        (crossing, not_crossing)
+    }
+
+    /// Whether the word can be read starting at this point and going in this direction.
+    fn has_word_at(&self, start: Point, direction: Direction, word: &Word) -> bool {
+        word.graphemes().enumerate().all(|(n, grapheme)| {
+            direction
+                .nth_point(start, n)
+                .and_then(|point| self.get(point))
+                .map(Grapheme::as_str)
+                == Some(grapheme)
+        })
+    }
+
+    /// Whether any word can be read through this point.
+    ///
+    /// Because this is only checked for cells not part of any placed word,
+    /// any word found through them is an accidental copy.
+    fn has_copy_through(&self, point: Point, words: &[Word], directions: &[Direction]) -> bool {
+        words.iter().any(|word| {
+            directions.iter().any(|&direction| {
+                // Walk back to where the word would start if this point held its nth character
+                word.graphemes().enumerate().any(|(n, grapheme)| {
+                    grapheme == self[point].as_str()
+                        && direction
+                            .opposite()
+                            .nth_point(point, n)
+                            .is_some_and(|start| self.has_word_at(start, direction, word))
+                })
+            })
+        })
+    }
+
+    /// Solves a copy of this grid and returns the index of the first word that does not occur exactly once.
+    fn ambiguous_word(&self, words: &[Word], directions: &[Direction]) -> Option<usize> {
+        let mut crossword = Crossword {
+            headers: Vec::new(),
+            cells: self.cells.iter().cloned().map(Cell::new).collect(),
+            width: self.width,
+            height: self.height,
+            words: words
+                .iter()
+                .map(|word| Word::new(word.str.clone()))
+                .collect(),
+            solved: false,
+            matching: Matching::default(),
+            directions: directions.to_vec(),
+        };
+
+        crossword
+            .solve(false, directions, true)
+            .words
+            .iter()
+            .position(|word| word.placements.len() != 1)
+    }
+
+    /// Fills all empty cells, avoiding characters that would create another occurrence of a word.
+    fn fill(
+        &mut self,
+        words: &[Word],
+        options: &GenOptions,
+        rng: &mut impl Rng,
+    ) -> Result<(), GenError> {
+        let word_graphemes = self
+            .cells
+            .iter()
+            .filter(|grapheme| !grapheme.is_empty())
+            .cloned()
+            .collect();
+        let mut filler = Filler::new(&options.fill, words, word_graphemes)?;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let point = Point { x, y };
+                if !self[point].is_empty() {
+                    continue;
+                }
+
+                for _ in 0..MAX_FILL_ATTEMPTS {
+                    self[point] = filler.next(rng);
+                    if !self.has_copy_through(point, words, &options.directions) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Index<Point> for Grid {
-    type Output = char;
+    type Output = Grapheme;
 
     fn index(&self, point: Point) -> &Self::Output {
         &self.cells[self.get_index(point)]
@@ -84,185 +272,304 @@ impl IndexMut<Point> for Grid {
     }
 }
 
-pub fn gen(watch: bool, words: &[Word], writer: &mut impl io::Write) -> Result<(), Error> {
-    let mut rng = SmallRng::from_entropy();
-    gen_internal(watch, words, writer, &mut rng)
+/// Options for generating a crossword.
+#[derive(Debug)]
+pub struct GenOptions {
+    /// The directions words are allowed to be written in.
+    pub directions: Vec<Direction>,
+    /// The seed for the random number generator.
+    ///
+    /// The same seed and words always result in the same crossword for a given version of crustword.
+    /// If this is `None`, a random seed is used.
+    pub seed: Option<u64>,
+    /// How much words cross each other by sharing characters, from `0.0` to `1.0`.
+    ///
+    /// This is the probability of a word being placed across another word if possible.
+    /// At `0.0`, words never share any characters.
+    pub overlap: f64,
+    /// The width of the grid or `None` to choose one randomly.
+    pub width: Option<usize>,
+    /// The height of the grid or `None` to choose one randomly.
+    pub height: Option<usize>,
+    /// How the cells that are not part of any word are filled.
+    pub fill: Fill,
+    /// A message to hide in the cells not part of any word, in reading order before the fill.
+    ///
+    /// Like in phrases, spaces and punctuation are left out.
+    pub message: Option<String>,
+}
+
+impl Default for GenOptions {
+    fn default() -> Self {
+        Self {
+            directions: Direction::ALL.to_vec(),
+            seed: None,
+            overlap: 0.5,
+            width: None,
+            height: None,
+            fill: Fill::Words,
+            message: None,
+        }
+    }
+}
+
+/// Information about how a crossword was generated.
+#[derive(Debug, PartialEq)]
+pub struct Generated {
+    /// The seed that was used.
+    pub seed: u64,
+    /// How many times the grid was filled again or the words were placed again
+    /// because a word occurred more than once.
+    ///
+    /// In the generated crossword, every word occurs exactly once.
+    pub rerolls: usize,
+}
+
+/// Generates a crossword in which every word occurs exactly once.
+pub fn gen(
+    watch: bool,
+    words: &[Word],
+    options: &GenOptions,
+    writer: &mut impl io::Write,
+) -> Result<Generated, GenError> {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    // Unlike `SmallRng` and `StdRng`, this RNG is guaranteed to be reproducible across platforms
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let rerolls = gen_internal(watch, words, options, writer, &mut rng)?;
+    Ok(Generated { seed, rerolls })
+}
+
+/// A word placed in the grid along with the points it can still be moved to if we have to backtrack.
+struct Frame {
+    candidates: Vec<Position>,
+    /// The cells the word was placed on.
+    points: Vec<Point>,
+    /// The cells that were empty before the word was placed.
+    filled: Vec<Point>,
 }
 
+/// Returns how many times the grid was filled again or the words were placed again.
 fn gen_internal(
     mut watch: bool,
     words: &[Word],
+    options: &GenOptions,
     writer: &mut impl io::Write,
     rng: &mut impl Rng,
-) -> Result<(), Error> {
-    fn index(
-        grid: &mut Grid,
-        points: impl Iterator<Item = Point> + Clone,
-        word: impl Iterator<Item = char>,
-    ) -> ControlFlow<()> {
-        if points.clone().all(|point| grid.get(point) == Some(&'\0')) {
-            for (point, char) in points.zip(word) {
-                grid[point] = char;
+) -> Result<usize, GenError> {
+    let message = options
+        .message
+        .as_ref()
+        .map(|message| Word::new(message.as_str().into()));
+    let mut grid = Grid::new(words, message.as_ref(), options, rng)?;
+
+    if options.directions.is_empty() {
+        return Err(GenError::NoDirections);
+    }
+    let overlap = Bernoulli::new(options.overlap).map_err(|_| GenError::InvalidOverlap)?;
+
+    // A word inside another word occurs at least twice no matter where they are placed
+    let contained = words.iter().find(|word| {
+        words
+            .iter()
+            .any(|other| other.len > word.len && other.grid_str.contains(&*word.grid_str))
+    });
+    if let Some(word) = contained {
+        return Err(GenError::NotUnique(word.str.to_string()));
+    }
+
+    // Only grids of a random size are made bigger
+    let can_grow = options.width.is_none() && options.height.is_none();
+    let mut growths = 0;
+    let mut rerolls = 0;
+    let mut ambiguous_word = 0;
+    let mut message_attempts = 0;
+
+    let mut placements = 0;
+
+    while placements < MAX_PLACEMENTS {
+        grid.cells.fill(Grapheme::default());
+        match place_words(watch, &mut grid, words, options, &overlap, rng) {
+            Err(GenError::NoRoom(_)) if can_grow && growths < MAX_GROWTHS => {
+                grid = Grid::empty(grid.width + 1, grid.height + 1);
+                growths += 1;
+                continue;
+            }
+            result => result?,
+        }
+
+        if let Some(message) = &message {
+            // Any cell not taken by the message would be filled and read as part of it
+            let empty_cells = grid.cells.iter().filter(|grapheme| grapheme.is_empty());
+            if empty_cells.count() != message.len {
+                if message_attempts == MAX_MESSAGE_ATTEMPTS {
+                    return Err(GenError::MessageDoesNotFit);
+                }
+                message_attempts += 1;
+                // Try another size if it is random
+                grid = Grid::new(words, Some(message), options, rng)?;
+                continue;
+            }
+
+            // Hide the message in reading order so that it is left over after solving
+            let empty_cells = grid.cells.iter_mut().filter(|grapheme| grapheme.is_empty());
+            for (cell, grapheme) in empty_cells.zip(message.graphemes()) {
+                *cell = grapheme.into();
+            }
+        }
+        placements += 1;
+
+        // Make sure the crossword has exactly one solution
+        let unfilled_cells = grid.cells.clone();
+        for _ in 0..MAX_REROLLS {
+            grid.fill(words, options, rng)?;
+
+            if let Some(i) = grid.ambiguous_word(words, &options.directions) {
+                ambiguous_word = i;
+                grid.cells.clone_from(&unfilled_cells);
+                rerolls += 1;
+            } else {
+                watch = false;
+
+                return if write_directions(&options.directions, writer).is_err()
+                    || write_grid(watch, &grid, writer).is_err()
+                    || write_words(words, writer).is_err()
+                {
+                    Err(GenError::WritingFailed)
+                } else {
+                    Ok(rerolls)
+                };
             }
-            ControlFlow::Break(())
-        } else {
-            ControlFlow::Continue(())
         }
     }
 
-    let mut grid = Grid::new(words, rng)?;
+    Err(GenError::NotUnique(words[ambiguous_word].str.to_string()))
+}
 
-    let direction_range = Uniform::from(0..8);
+/// Places all words in the grid, backtracking if a word does not fit anymore.
+///
+/// If watching, every step is drawn on stdout, even if the crossword is written elsewhere.
+fn place_words(
+    watch: bool,
+    grid: &mut Grid,
+    words: &[Word],
+    options: &GenOptions,
+    overlap: &Bernoulli,
+    rng: &mut impl Rng,
+) -> Result<(), GenError> {
+    let mut frames = Vec::<Frame>::with_capacity(words.len());
+    // The furthest we got, which is the word that is the hardest to place
+    let mut stuck = 0;
 
-    for word in words {
-        let mut point = grid.get_rand_point(rng);
-        let mut direction = direction_range.sample(rng);
+    for _ in 0..MAX_ATTEMPTS {
+        let i = frames.len();
+        let word = if let Some(word) = words.get(i) {
+            word
+        } else {
+            return Ok(());
+        };
 
-        loop {
-            match direction {
-                0 => {
-                    // North
-                    if let Some(result) = point.y.checked_sub(word.len) {
-                        let points = (result..point.y).map(|y| Point { y, ..point });
-                        if let ControlFlow::Break(()) = index(&mut grid, points, word.chars().rev())
-                        {
-                            break;
-                        }
-                    }
-                }
-                1 => {
-                    // Northeast
-                    if let Some(result) = point.y.checked_sub(word.len) {
-                        let points = (result..point.y)
-                            .rev()
-                            .enumerate()
-                            .map(|(x, y)| Point { y, x: point.x + x });
-                        if let ControlFlow::Break(()) = index(&mut grid, points, word.chars()) {
-                            break;
-                        }
-                    }
-                }
-                2 => {
-                    // East
-                    let points = (point.x..point.x + word.len).map(|x| Point { x, ..point });
-                    if let ControlFlow::Break(()) = index(&mut grid, points, word.chars()) {
-                        break;
-                    }
-                }
-                3 => {
-                    // Southeast
-                    let points = (point.y..point.y + word.len)
-                        .enumerate()
-                        .map(|(x, y)| Point { y, x: point.x + x });
-                    if let ControlFlow::Break(()) = index(&mut grid, points, word.chars()) {
-                        break;
-                    }
-                }
-                4 => {
-                    // South
-                    let points = (point.y..point.y + word.len).map(|y| Point { y, ..point });
-                    if let ControlFlow::Break(()) = index(&mut grid, points, word.chars()) {
-                        break;
-                    }
-                }
-                5 => {
-                    // Southwest
-                    let points = (point.y..point.y + word.len)
-                        .rev()
-                        .enumerate()
-                        .map(|(x, y)| Point { x, y });
-                    if let ControlFlow::Break(()) = index(&mut grid, points, word.chars().rev()) {
-                        break;
-                    }
-                }
-                6 => {
-                    // West
-                    if let Some(result) = point.x.checked_sub(word.len) {
-                        let points = (result..point.x).map(|x| Point { x, ..point });
-                        if let ControlFlow::Break(()) = index(&mut grid, points, word.chars().rev())
-                        {
-                            break;
-                        }
-                    }
-                }
-                7 => {
-                    // Northwest
-                    if point.x.checked_sub(word.len).is_some() {
-                        if let Some(result) = point.y.checked_sub(word.len) {
-                            let points = (result..point.y)
-                                .rev()
-                                .enumerate()
-                                .map(|(x, y)| Point { y, x: point.x - x });
-                            if let ControlFlow::Break(()) = index(&mut grid, points, word.chars()) {
-                                break;
-                            }
-                        }
+        let placed: Vec<_> = frames.iter().map(|frame| frame.points.as_slice()).collect();
+        let (mut crossing, mut not_crossing) = grid.placements(word, &options.directions, &placed);
+        crossing.shuffle(rng);
+        not_crossing.shuffle(rng);
+
+        // The candidates are tried from the back
+        let candidates = if options.overlap == 0.0 {
+            not_crossing
+        } else if overlap.sample(rng) {
+            not_crossing.append(&mut crossing);
+            not_crossing
+        } else {
+            crossing.append(&mut not_crossing);
+            crossing
+        };
+        frames.push(Frame {
+            candidates,
+            points: Vec::new(),
+            filled: Vec::new(),
+        });
+
+        // Place the word or go back to the previous words and move them
+        // until there is a word that can be placed
+        while let Some(i) = frames.len().checked_sub(1) {
+            let frame = &mut frames[i];
+            for point in frame.filled.drain(..) {
+                grid[point] = Grapheme::default();
+            }
+
+            if let Some((start, direction)) = frame.candidates.pop() {
+                frame.points = direction.points(start, words[i].len).unwrap_or_default();
+                for (&point, grapheme) in frame.points.iter().zip(words[i].graphemes()) {
+                    if grid[point].is_empty() {
+                        grid[point] = grapheme.into();
+                        frame.filled.push(point);
                     }
                 }
-                _ => {
-                    point = grid.get_rand_point(rng);
-                    direction = direction_range.sample(rng);
-                    continue;
-                }
+                break;
+            } else {
+                stuck = stuck.max(i);
+                frames.pop();
             }
-            direction += 1;
+        }
+
+        if frames.is_empty() {
+            break;
         }
 
         if watch {
-            if write_grid(watch, &grid, writer, rng).is_err()
-                || write_words(words, writer).is_err()
-                || writeln!(writer).is_err()
-                || escape_sequences::cursor_up(writer, grid.height + 2).is_err()
-            {
-                return Err("writing failed");
+            if write_frame(grid, words, &mut io::stdout()).is_err() {
+                return Err(GenError::WritingFailed);
             }
             thread::sleep(time::Duration::from_secs(1));
         }
     }
 
-    watch = false;
+    // Leave the grid in a clean state for the next try
+    grid.cells.fill(Grapheme::default());
 
-    if write_grid(watch, &grid, writer, rng).is_err() || write_words(words, writer).is_err() {
-        Err("writing failed")
-    } else {
-        Ok(())
+    Err(GenError::NoRoom(words[stuck].str.to_string()))
+}
+
+/// Writes a `directions` header if the words are limited to some directions,
+/// since every word only occurs once in those.
+fn write_directions(directions: &[Direction], writer: &mut impl io::Write) -> io::Result<()> {
+    if Direction::ALL
+        .iter()
+        .all(|direction| directions.contains(direction))
+    {
+        return Ok(());
     }
+
+    let directions: Vec<_> = directions.iter().map(Direction::to_string).collect();
+    writeln!(writer, "directions: {}", directions.join(", "))?;
+    writeln!(writer)
 }
 
-fn write_grid(
-    watch: bool,
-    grid: &Grid,
-    writer: &mut impl io::Write,
-    rng: &mut impl Rng,
-) -> io::Result<()> {
-    let original_fill_characters = grid.cells.iter().filter(|&char| char != &'\0');
-    let mut fill_characters = original_fill_characters.clone();
-    let distribution = Bernoulli::new(0.5).unwrap();
+/// Writes a step of placing the words and moves the cursor back up for the next one.
+fn write_frame(grid: &Grid, words: &[Word], writer: &mut impl io::Write) -> io::Result<()> {
+    write_grid(true, grid, writer)?;
+    write_words(words, writer)?;
+    writeln!(writer)?;
+    escape_sequences::cursor_up(writer, grid.height + 2)
+}
+
+/// Writes the grid, showing empty cells as blank if watching.
+fn write_grid(watch: bool, grid: &Grid, writer: &mut impl io::Write) -> io::Result<()> {
+    for (y, row) in grid.cells.chunks_exact(grid.width).enumerate() {
+        // Indent a first row starting with `#` so that it is not read as a comment
+        if y == 0 && row[0].as_str().starts_with('#') {
+            write!(writer, " ")?;
+        }
 
-    for row in grid.cells.chunks_exact(grid.width) {
         let mut row = row.iter().peekable();
-        while let Some(char) = row.next() {
-            let written_char = if char == &'\0' {
-                if watch {
-                    &' '
-                } else {
-                    if distribution.sample(rng) {
-                        fill_characters.next();
-                    }
-                    let fill_character = fill_characters.next().unwrap_or_else(|| {
-                        // Refill the fill characters
-                        fill_characters = original_fill_characters.clone();
-                        fill_characters.next().unwrap()
-                    });
-                    write!(writer, "{}", fill_character)?;
-                    fill_character
-                }
+        while let Some(grapheme) = row.next() {
+            if watch && grapheme.is_empty() {
+                write!(writer, " ")?;
             } else {
-                write!(writer, "{}", char)?;
-                char
-            };
+                write!(writer, "{}", grapheme)?;
+            }
 
-            if written_char.width() <= Some(1) && row.peek().is_some() {
+            if grapheme.width() <= 1 && row.peek().is_some() {
                 write!(writer, " ")?;
             }
         }
@@ -280,12 +587,17 @@ fn write_words(words: &[Word], writer: &mut impl io::Write) -> io::Result<()> {
     // depending on whether there are overall more full-width characters than half-width characters
     let use_full_width = words
         .clone()
-        .filter(|word| word.chars().filter(|char| char.width() == Some(2)).count() > word.len / 2)
+        .filter(|word| {
+            word.graphemes()
+                .filter(|&grapheme| grapheme::width(grapheme) == 2)
+                .count()
+                > word.len / 2
+        })
         .count()
         > words.len() / 2;
 
     while let Some(word) = words.next() {
-        write!(writer, "{}", word.str)?;
+        write!(writer, "{}", word.quoted())?;
         if words.peek().is_some() {
             if use_full_width {
                 write!(writer, "　")?;
@@ -301,30 +613,61 @@ fn write_words(words: &[Word], writer: &mut impl io::Write) -> io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crossword::{Crossword, ENGLISH};
     use indoc::indoc;
 
+    /// Generates a crossword, parses it and checks that solving it finds every word exactly once.
+    ///
+    /// Returns the generated text and the solved crossword.
+    fn gen_and_solve(words: &[Word], options: &GenOptions) -> (String, Crossword<'static>) {
+        let mut writer = Vec::new();
+        gen(false, words, options, &mut writer).unwrap();
+
+        let crossword_str = String::from_utf8(writer).unwrap();
+        let mut crossword = Crossword::try_from(crossword_str.as_ref())
+            .unwrap()
+            .into_owned();
+        // Words only need to be unique in the directions from the header
+        let directions = crossword.directions().to_vec();
+        let solution = crossword.solve(false, &directions, true);
+        assert!(
+            solution.words.iter().all(|word| word.placements.len() == 1),
+            "{}",
+            crossword_str
+        );
+
+        (crossword_str, crossword)
+    }
+
     #[test]
     fn test_gen() {
-        let mut rng = SmallRng::seed_from_u64(0);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
         let mut writer = Vec::new();
+        let options = GenOptions {
+            overlap: 0.0,
+            ..GenOptions::default()
+        };
 
-        gen_internal(
-            false,
-            &[Word::new("hello".into()), Word::new("world".into())],
-            &mut writer,
-            &mut rng,
-        )
-        .unwrap();
+        assert_eq!(
+            gen_internal(
+                false,
+                &[Word::new("hello".into()), Word::new("world".into())],
+                &options,
+                &mut writer,
+                &mut rng,
+            ),
+            Ok(0)
+        );
         assert_eq!(
             std::str::from_utf8(&writer).unwrap(),
             indoc!(
-                "o l e h o l o
-                 l l e w r l o
-                 l l h w r l o
-                 l e w r d o l
-                 h o l l e h w
-                 o l w o r l d
-                 o l e w r d o
+                "w o r l o l e w
+                 r l o w o r l d
+                 l e w r d o l e
+                 h o l l e h w o
+                 r d o l h w o r
+                 l d o l l e w r
+                 l o l h w r l d
 
                  hello world"
             )
@@ -339,6 +682,7 @@ mod tests {
                 Word::new("hello".into()),
                 Word::new("クロスワード".into()),
             ],
+            &options,
             &mut writer,
             &mut rng,
         )
@@ -346,15 +690,347 @@ mod tests {
         assert_eq!(
             std::str::from_utf8(&writer).unwrap(),
             indoc!(
-                "クh b h ロe
-                 ロe l l スl
-                 スl å å l h
-                 ワl h o a j
-                 ーo a クh b
-                 ドロj e l l
+                "l ドワロl e j a å l o
+                 l ドースクl h a h l o
+                 l ーワスロクl h j a å
+                 l b o ドo ースロクe j
+                 a h l l ドーワスロクo
+                 ドーl ワスクe j a å b
+                 o e l ースクe h j h å
+                 h b j a h å l b o ドワ
+                 スクe h a h l o ドワス
 
                  blåhaj hello クロスワード"
             )
         );
     }
+
+    #[test]
+    fn test_gen_seed() {
+        let words = [Word::new("seed".into()), Word::new("grid".into())];
+        let options = GenOptions {
+            seed: Some(42),
+            ..GenOptions::default()
+        };
+
+        let mut writer1 = Vec::new();
+        let mut writer2 = Vec::new();
+        let generated1 = gen(false, &words, &options, &mut writer1).unwrap();
+        let generated2 = gen(false, &words, &options, &mut writer2).unwrap();
+        assert_eq!(generated1.seed, 42);
+        assert_eq!(generated1, generated2);
+        assert_eq!(writer1, writer2);
+    }
+
+    #[test]
+    fn test_overlap() {
+        let grid = Grid {
+            cells: ["a", "b", "", ""].map(Grapheme::from).to_vec(),
+            width: 4,
+            height: 1,
+        };
+        let start = |x| Point { x, y: 0 };
+        let east = Direction::East;
+
+        assert_eq!(
+            grid.overlap(start(1), east, &Word::new("bcd".into()), &[]),
+            Some(1)
+        );
+        assert_eq!(
+            grid.overlap(start(2), east, &Word::new("cd".into()), &[]),
+            Some(0)
+        );
+        assert_eq!(
+            grid.overlap(start(0), east, &Word::new("xy".into()), &[]),
+            None
+        );
+        assert_eq!(
+            grid.overlap(start(0), east, &Word::new("ab".into()), &[]),
+            None
+        );
+        assert_eq!(
+            grid.overlap(start(2), east, &Word::new("cde".into()), &[]),
+            None
+        );
+        assert_eq!(
+            grid.overlap(start(1), Direction::West, &Word::new("bab".into()), &[]),
+            None
+        );
+
+        // "ab" would be contained in "abc"
+        let abc = Word::new("abc".into());
+        assert_eq!(grid.overlap(start(0), east, &abc, &[]), Some(2));
+        assert_eq!(
+            grid.overlap(start(0), east, &abc, &[&[start(0), start(1)]]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gen_overlap() {
+        let words = [
+            Word::new("crossing".into()),
+            Word::new("words".into()),
+            Word::new("share".into()),
+            Word::new("letters".into()),
+        ];
+        let options = GenOptions {
+            seed: Some(0),
+            overlap: 1.0,
+            ..GenOptions::default()
+        };
+
+        gen_and_solve(&words, &options);
+    }
+
+    #[test]
+    fn test_gen_graphemes() {
+        let words = [
+            Word::new("👍🏽👍🏿".into()),
+            Word::new("नमस्ते".into()),
+            Word::new("a\u{30a}sa\u{30a}".into()),
+        ];
+        let options = GenOptions {
+            seed: Some(0),
+            fill: Fill::Letters,
+            ..GenOptions::default()
+        };
+
+        let (_, crossword) = gen_and_solve(&words, &options);
+        assert!(crossword.cells.iter().all(|cell| !cell.grapheme.is_empty()));
+    }
+
+    #[test]
+    fn test_gen_phrases() {
+        let words = [Word::new("ICE CREAM".into()), Word::new("T-SHIRT".into())];
+        let options = GenOptions {
+            seed: Some(0),
+            fill: Fill::Letters,
+            ..GenOptions::default()
+        };
+
+        let (crossword_str, crossword) = gen_and_solve(&words, &options);
+        assert!(crossword_str.ends_with("\"ICE CREAM\" T-SHIRT"));
+        assert_eq!(crossword.words[0].str, "ICE CREAM");
+        assert!(crossword
+            .cells
+            .iter()
+            .all(|cell| cell.grapheme.as_str() != " "));
+    }
+
+    #[test]
+    fn test_gen_message() {
+        let words = [Word::new("cat".into()), Word::new("dog".into())];
+        let mut options = GenOptions {
+            seed: Some(0),
+            overlap: 0.0,
+            width: Some(4),
+            height: Some(3),
+            message: Some("Hey, you!".into()),
+            ..GenOptions::default()
+        };
+
+        // The words take up 6 of the 12 cells, which leaves exactly enough room for the message
+        let (_, crossword) = gen_and_solve(&words, &options);
+        assert_eq!(crossword.leftover(), "Heyyou");
+
+        // Any other free cell would have to be filled and end up in the message
+        for message in ["Hi", "A much longer message"] {
+            options.message = Some(message.into());
+            assert_eq!(
+                gen(false, &words, &options, &mut Vec::new()),
+                Err(GenError::MessageDoesNotFit)
+            );
+        }
+
+        // Grids of a random size are picked to fit the message
+        options.width = None;
+        options.height = None;
+        options.overlap = 0.5;
+        for message in ["Hi", "A much longer message"] {
+            options.message = Some(message.into());
+            for seed in 0..10 {
+                options.seed = Some(seed);
+                let (_, crossword) = gen_and_solve(&words, &options);
+                assert_eq!(crossword.leftover(), message.replace(' ', ""));
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_grid_hash() {
+        let grid = Grid {
+            cells: ["#", "a", "#", "b"].map(Grapheme::from).to_vec(),
+            width: 2,
+            height: 2,
+        };
+        let mut writer = Vec::new();
+
+        write_grid(false, &grid, &mut writer).unwrap();
+        write_words(&[Word::new("ab".into())], &mut writer).unwrap();
+
+        let crossword_str = String::from_utf8(writer).unwrap();
+        assert_eq!(crossword_str, " # a\n# b\n\nab");
+        let crossword = Crossword::try_from(crossword_str.as_ref()).unwrap();
+        assert_eq!(crossword.height, 2);
+    }
+
+    #[test]
+    fn test_place_words_containing() {
+        // "sunflower" must not be placed on top of "sun", which would then only be found inside it
+        let words = [Word::new("sun".into()), Word::new("sunflower".into())];
+        let options = GenOptions {
+            directions: vec![Direction::East],
+            overlap: 1.0,
+            ..GenOptions::default()
+        };
+        let overlap = Bernoulli::new(options.overlap).unwrap();
+
+        for seed in 0..10 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut place =
+                |grid: &mut Grid| place_words(false, grid, &words, &options, &overlap, &mut rng);
+
+            let mut grid = Grid::empty(9, 1);
+            assert_eq!(
+                place(&mut grid),
+                Err(GenError::NoRoom("sunflower".to_string()))
+            );
+
+            let mut grid = Grid::empty(9, 2);
+            place(&mut grid).unwrap();
+            let empty_cells = grid.cells.iter().filter(|grapheme| grapheme.is_empty());
+            assert_eq!(empty_cells.count(), 6);
+        }
+
+        assert_eq!(
+            gen(false, &words, &options, &mut Vec::new()),
+            Err(GenError::NotUnique("sun".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_place_words_backtracking() {
+        // There are only a few ways to fit these words,
+        // so words placed early often have to be moved
+        let words = [
+            Word::new("ab".into()),
+            Word::new("cde".into()),
+            Word::new("fgh".into()),
+        ];
+        let options = GenOptions {
+            directions: vec![Direction::East, Direction::South],
+            overlap: 0.0,
+            ..GenOptions::default()
+        };
+        let overlap = Bernoulli::new(options.overlap).unwrap();
+
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut grid = Grid::empty(3, 3);
+
+            place_words(false, &mut grid, &words, &options, &overlap, &mut rng).unwrap();
+
+            assert_eq!(
+                grid.cells
+                    .iter()
+                    .filter(|grapheme| grapheme.is_empty())
+                    .count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_place_words_no_room() {
+        let words = [Word::new("ab".into()), Word::new("cd".into())];
+        let options = GenOptions {
+            directions: vec![Direction::East],
+            overlap: 0.0,
+            ..GenOptions::default()
+        };
+        let overlap = Bernoulli::new(options.overlap).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut grid = Grid::empty(2, 1);
+
+        assert_eq!(
+            place_words(false, &mut grid, &words, &options, &overlap, &mut rng,),
+            Err(GenError::NoRoom("cd".to_string()))
+        );
+        assert!(grid.cells.iter().all(Grapheme::is_empty));
+    }
+
+    #[test]
+    fn test_gen_size() {
+        let words = [Word::new("fixed".into()), Word::new("size".into())];
+        let mut options = GenOptions {
+            seed: Some(0),
+            width: Some(15),
+            height: Some(12),
+            ..GenOptions::default()
+        };
+
+        let (_, crossword) = gen_and_solve(&words, &options);
+        assert_eq!((crossword.width, crossword.height), (15, 12));
+
+        options.width = Some(4);
+        options.height = Some(4);
+        assert_eq!(
+            gen(false, &words, &options, &mut Vec::new()),
+            Err(GenError::NoRoom("fixed".to_string()))
+        );
+
+        options.height = Some(0);
+        assert_eq!(
+            gen(false, &words, &options, &mut Vec::new()),
+            Err(GenError::InvalidSize)
+        );
+    }
+
+    #[test]
+    fn test_gen_fill() {
+        let words = [
+            Word::new("cat".into()),
+            Word::new("dog".into()),
+            Word::new("bird".into()),
+        ];
+
+        for fill in [Fill::Words, Fill::Letters, Fill::Frequencies(ENGLISH)] {
+            for seed in 0..10 {
+                let options = GenOptions {
+                    seed: Some(seed),
+                    fill: fill.clone(),
+                    ..GenOptions::default()
+                };
+
+                // The fill characters must not create another occurrence of any word
+                gen_and_solve(&words, &options);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_not_unique() {
+        // Wherever "ab" is placed, "ba" can be read backwards
+        let words = [Word::new("ab".into()), Word::new("ba".into())];
+        let options = GenOptions {
+            seed: Some(0),
+            fill: Fill::Letters,
+            ..GenOptions::default()
+        };
+
+        assert_eq!(
+            gen(false, &words, &options, &mut Vec::new()),
+            Err(GenError::NotUnique("ab".to_string()))
+        );
+
+        // Only allowing one direction makes the words distinct again, which solving follows from the header
+        let options = GenOptions {
+            directions: vec![Direction::East],
+            ..options
+        };
+        let (crossword_str, crossword) = gen_and_solve(&words, &options);
+        assert!(crossword_str.starts_with("directions: east\n\n"));
+        assert_eq!(crossword.directions(), [Direction::East]);
+    }
 }
@@ -0,0 +1,93 @@
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// One user-perceived character, which is what a cell of the grid holds.
+///
+/// This is an extended grapheme cluster and can consist of several `char`s,
+/// such as a letter followed by combining accents, a Devanagari conjunct or an emoji with a skin tone modifier.
+/// The empty grapheme stands for an empty cell while generating.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Grapheme(Box<str>);
+
+impl Grapheme {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of terminal columns this takes up, which is 2 for full-width characters.
+    pub fn width(&self) -> usize {
+        width(&self.0)
+    }
+}
+
+/// The number of terminal columns a grapheme takes up.
+///
+/// The widths of its `char`s are added up but capped at 2 because terminals draw
+/// sequences such as emoji with modifiers as a single full-width character.
+pub fn width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+impl From<char> for Grapheme {
+    fn from(char: char) -> Self {
+        Self(char.to_string().into())
+    }
+}
+
+impl From<&str> for Grapheme {
+    fn from(str: &str) -> Self {
+        Self(str.into())
+    }
+}
+
+impl fmt::Display for Grapheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Whether this grapheme only separates the parts of a phrase, such as a space or a hyphen.
+///
+/// Separators are shown in the word list but not written in the grid.
+pub fn is_separator(grapheme: &str) -> bool {
+    grapheme.chars().all(|char| {
+        char.is_whitespace()
+            || char.is_ascii_punctuation()
+            || matches!(char, '‐' | '‑' | '–' | '—' | '‘' | '’' | '·' | '・')
+    })
+}
+
+/// Splits the text into extended grapheme clusters.
+pub fn graphemes(str: &str) -> impl DoubleEndedIterator<Item = &str> {
+    str.graphemes(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphemes() {
+        assert_eq!(
+            graphemes("a\u{30a}👍🏽क्ष🇸🇪").collect::<Vec<_>>(),
+            vec!["a\u{30a}", "👍🏽", "क्ष", "🇸🇪"]
+        );
+
+        let width = |str| Grapheme::from(str).width();
+        assert_eq!(width("a\u{30a}"), 1);
+        assert_eq!(width("ク"), 2);
+        assert_eq!(width("👍🏽"), 2);
+        assert_eq!(width("🇸🇪"), 2);
+
+        assert!(is_separator(" "));
+        assert!(is_separator("-"));
+        assert!(is_separator("’"));
+        assert!(!is_separator("a"));
+        assert!(!is_separator("👍🏽"));
+    }
+}
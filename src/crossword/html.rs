@@ -0,0 +1,155 @@
+use super::Crossword;
+use crate::util::escape;
+use std::fmt::{self, Write};
+
+/// Every cell is two characters wide like in the terminal, which leaves room for full-width characters.
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+.page + .page { break-before: page; }
+.grid { position: relative; display: inline-grid; font-family: monospace; font-size: 1.5em; border: 1px solid; }
+.grid span { width: 2ch; height: 2ch; line-height: 2ch; text-align: center; }
+.grid svg { position: absolute; inset: 0; width: 100%; height: 100%; }
+.grid line { stroke: #0006; stroke-width: 0.7; stroke-linecap: round; }
+.words { columns: 4; list-style: none; padding: 0; }
+.words .not-found { text-decoration: line-through; }
+dl { display: grid; grid-template-columns: max-content auto; gap: 0 1em; }
+dd { margin: 0; }
+";
+
+impl Crossword<'_> {
+    /// Renders the crossword as a printable HTML page.
+    ///
+    /// If `answer_key` is set and the crossword has been solved,
+    /// a second page shows the grid with a line over every word that was found.
+    pub fn to_html(&self, answer_key: bool) -> String {
+        let mut html = String::new();
+        // Only strings and numbers are formatted, so appending them to `html` always succeeds
+        self.write_html(&mut html, answer_key).unwrap();
+        html
+    }
+
+    fn write_html(&self, html: &mut String, answer_key: bool) -> fmt::Result {
+        let title = self.title().unwrap_or("Crossword");
+
+        writeln!(html, "<!DOCTYPE html>")?;
+        writeln!(html, "<html>")?;
+        writeln!(html, "<head>")?;
+        writeln!(html, "<meta charset=\"utf-8\">")?;
+        writeln!(html, "<title>{}</title>", escape(title))?;
+        writeln!(html, "<style>{}</style>", STYLE)?;
+        writeln!(html, "</head>")?;
+        writeln!(html, "<body>")?;
+
+        writeln!(html, "<section class=\"page\">")?;
+        self.write_html_title(html, title)?;
+        self.write_html_grid(html, false)?;
+        writeln!(html, "<ul class=\"words\">")?;
+        for word in &self.words {
+            writeln!(html, "<li>{}</li>", escape(&word.str))?;
+        }
+        writeln!(html, "</ul>")?;
+        writeln!(html, "</section>")?;
+
+        if answer_key && self.solved {
+            writeln!(html, "<section class=\"page\">")?;
+            writeln!(html, "<h1>{} – Answer key</h1>", escape(title))?;
+            self.write_html_grid(html, true)?;
+            writeln!(html, "<ul class=\"words\">")?;
+            for word in &self.words {
+                if word.placements.is_empty() {
+                    writeln!(html, "<li class=\"not-found\">{}</li>", escape(&word.str))?;
+                } else {
+                    writeln!(html, "<li>{}</li>", escape(&word.str))?;
+                }
+            }
+            writeln!(html, "</ul>")?;
+            writeln!(html, "</section>")?;
+        }
+
+        writeln!(html, "</body>")?;
+        writeln!(html, "</html>")
+    }
+
+    fn write_html_title(&self, html: &mut String, title: &str) -> fmt::Result {
+        writeln!(html, "<h1>{}</h1>", escape(title))?;
+
+        let headers = self.headers.iter().filter(|(key, _)| key != "title");
+        if headers.clone().next().is_some() {
+            writeln!(html, "<dl>")?;
+            for (key, value) in headers {
+                writeln!(html, "<dt>{}</dt><dd>{}</dd>", escape(key), escape(value))?;
+            }
+            writeln!(html, "</dl>")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_html_grid(&self, html: &mut String, answers: bool) -> fmt::Result {
+        writeln!(
+            html,
+            "<div class=\"grid\" style=\"grid-template-columns: repeat({}, auto)\">",
+            self.width
+        )?;
+        for row in self.rows() {
+            for cell in row {
+                write!(html, "<span>{}</span>", escape(cell.grapheme.as_str()))?;
+            }
+            writeln!(html)?;
+        }
+
+        if answers {
+            // The cells are square, so one unit is one cell
+            writeln!(html, "<svg viewBox=\"0 0 {} {}\">", self.width, self.height)?;
+            for placement in self.words.iter().flat_map(|word| &word.placements) {
+                let end = placement.end();
+                writeln!(
+                    html,
+                    "<line x1=\"{}.5\" y1=\"{}.5\" x2=\"{}.5\" y2=\"{}.5\"/>",
+                    placement.start.x, placement.start.y, end.x, end.y
+                )?;
+            }
+            writeln!(html, "</svg>")?;
+        }
+
+        writeln!(html, "</div>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossword::Direction;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_html() {
+        // Full-width cells next to half-width ones, and a phrase that keeps its space in the word list
+        let mut crossword = Crossword::try_from(indoc! {
+            r#"title: 動物 & <ペット>
+               author: Jane Doe
+
+               ね こ x
+               い ぬ x
+               "い ぬ" ねこ とり"#
+        })
+        .unwrap();
+
+        let html = crossword.to_html(true);
+        assert!(html.contains("<title>動物 &amp; &lt;ペット&gt;</title>"));
+        assert!(html.contains("<dt>author</dt><dd>Jane Doe</dd>"));
+        assert!(html.contains("<span>ね</span><span>こ</span><span>x</span>\n"));
+        assert!(html.contains("<li>い ぬ</li>"));
+        // There is no answer key before solving
+        assert!(!html.contains("<svg"));
+
+        crossword.solve(false, &Direction::ALL, false);
+        let html = crossword.to_html(true);
+        assert_eq!(html.matches("<section").count(), 2);
+        assert!(html.contains("<h1>動物 &amp; &lt;ペット&gt; – Answer key</h1>"));
+        assert!(html.contains("<line x1=\"0.5\" y1=\"1.5\" x2=\"1.5\" y2=\"1.5\"/>"));
+        assert!(html.contains("<line x1=\"0.5\" y1=\"0.5\" x2=\"1.5\" y2=\"0.5\"/>"));
+        assert!(html.contains("<li class=\"not-found\">とり</li>"));
+        assert_eq!(crossword.to_html(false).matches("<section").count(), 1);
+    }
+}
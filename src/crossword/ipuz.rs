@@ -0,0 +1,211 @@
+use super::{graphemes, Crossword, Grapheme};
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The headers that correspond to ipuz fields. Other headers are not exported.
+const IPUZ_HEADERS: &[&str] = &[
+    "title",
+    "author",
+    "editor",
+    "copyright",
+    "publisher",
+    "publication",
+    "date",
+    "difficulty",
+    "origin",
+    "url",
+    "uniqueid",
+    "intro",
+    "explanation",
+    "annotation",
+    "notes",
+];
+
+const VERSION: &str = "http://ipuz.org/v2";
+const KIND: &str = "http://ipuz.org/wordsearch#1";
+
+/// A word search in the ipuz format as we write it.
+#[derive(Serialize)]
+struct Ipuz<'a> {
+    version: &'static str,
+    kind: [&'static str; 1],
+    #[serde(flatten)]
+    headers: Map<String, Value>,
+    dimensions: Dimensions,
+    puzzle: Vec<Vec<String>>,
+    solution: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Dimensions {
+    width: usize,
+    height: usize,
+}
+
+/// A word search in the ipuz format as we read it.
+#[derive(Deserialize)]
+pub struct IpuzInput {
+    pub kind: Vec<String>,
+    pub puzzle: Vec<Vec<IpuzCell>>,
+    #[serde(default)]
+    pub solution: IpuzSolution,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+impl IpuzInput {
+    /// The ipuz fields we understand as headers, in the order of [`IPUZ_HEADERS`].
+    pub fn headers(&self) -> Vec<(String, String)> {
+        IPUZ_HEADERS
+            .iter()
+            .filter_map(|&key| match self.fields.get(key)? {
+                Value::String(value) => Some((key.to_string(), value.clone())),
+                Value::Number(value) => Some((key.to_string(), value.to_string())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether this is a word search, which is the only kind of puzzle we support.
+    pub fn is_word_search(&self) -> bool {
+        self.kind
+            .iter()
+            .any(|kind| kind.starts_with("http://ipuz.org/wordsearch"))
+    }
+}
+
+/// A cell of the puzzle, which is either its content or an object with a `cell` field.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IpuzCell {
+    Char(String),
+    Number(u64),
+    Cell { cell: Box<IpuzCell> },
+}
+
+impl IpuzCell {
+    /// The content of the cell if it is a single grapheme.
+    pub fn grapheme(&self) -> Option<Grapheme> {
+        match self {
+            IpuzCell::Char(str) => {
+                let mut graphemes = graphemes(str);
+                graphemes
+                    .next()
+                    .filter(|_| graphemes.next().is_none())
+                    .map(Grapheme::from)
+            }
+            IpuzCell::Number(number) => {
+                char::from_digit(u32::try_from(*number).ok()?, 10).map(Grapheme::from)
+            }
+            IpuzCell::Cell { cell } => cell.grapheme(),
+        }
+    }
+}
+
+/// The words to find, either as a list or as the keys of an object that also has their positions.
+#[derive(Default, Deserialize)]
+#[serde(untagged)]
+pub enum IpuzSolution {
+    #[default]
+    None,
+    Words(Vec<String>),
+    Positions(WordKeys),
+}
+
+impl IpuzSolution {
+    pub fn into_words(self) -> Vec<String> {
+        match self {
+            IpuzSolution::None => Vec::new(),
+            IpuzSolution::Words(words) | IpuzSolution::Positions(WordKeys(words)) => words,
+        }
+    }
+}
+
+/// The keys of an object in the order they were given.
+pub struct WordKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for WordKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = WordKeys;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an object of words")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut keys = Vec::new();
+                while let Some((key, IgnoredAny)) = map.next_entry()? {
+                    keys.push(key);
+                }
+                Ok(WordKeys(keys))
+            }
+        }
+
+        deserializer.deserialize_map(KeysVisitor)
+    }
+}
+
+impl Crossword<'_> {
+    /// Writes the crossword as an ipuz word search.
+    pub fn to_ipuz(&self) -> String {
+        let headers = self
+            .headers
+            .iter()
+            .filter(|(key, _)| IPUZ_HEADERS.contains(&key.as_ref()))
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+        let ipuz = Ipuz {
+            version: VERSION,
+            kind: [KIND],
+            headers,
+            dimensions: Dimensions {
+                width: self.width,
+                height: self.height,
+            },
+            puzzle: self
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.grapheme.to_string()).collect())
+                .collect(),
+            solution: self.words.iter().map(|word| word.str.as_ref()).collect(),
+        };
+
+        // Serializing strings, numbers and maps cannot fail
+        serde_json::to_string_pretty(&ipuz).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_ipuz_round_trip() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "title: Fåglar
+             copyright: 2024 Crustword
+             language: Swedish
+
+             k r å k a
+             u g g l a
+             kråka uggla"
+        })
+        .unwrap();
+        let ipuz = crossword.to_ipuz();
+
+        assert!(ipuz.contains(r#""kind": ["#));
+        assert!(ipuz.contains(r#""copyright": "2024 Crustword""#));
+        assert!(ipuz.contains(r#""å","#));
+        // ipuz has no field for the language, so that header is lost
+        assert!(!ipuz.contains("Swedish"));
+        crossword.headers.retain(|(key, _)| key != "language");
+        assert_eq!(Crossword::from_ipuz(&ipuz, None), Ok(crossword));
+    }
+}
@@ -0,0 +1,221 @@
+use super::{Crossword, Direction};
+use crate::util::Point;
+use serde::{Deserialize, Serialize};
+
+/// The JSON form of a crossword, which is meant to be read by other programs.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonCrossword {
+    /// Metadata such as the title and author, in the order they were given.
+    #[serde(default, with = "headers")]
+    pub headers: Vec<(String, String)>,
+    #[serde(skip_deserializing)]
+    pub width: usize,
+    #[serde(skip_deserializing)]
+    pub height: usize,
+    /// The rows of the grid with one character per cell.
+    pub grid: Vec<String>,
+    pub words: Vec<String>,
+    /// Where each word was found, which is only present after solving.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub solution: Option<Vec<JsonSolvedWord>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JsonSolvedWord {
+    pub word: String,
+    /// Empty if the word was not found.
+    pub placements: Vec<JsonPlacement>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JsonPlacement {
+    pub start: Point,
+    pub end: Point,
+    pub direction: Direction,
+}
+
+impl From<&Crossword<'_>> for JsonCrossword {
+    fn from(crossword: &Crossword<'_>) -> Self {
+        let solution = crossword.solved.then(|| {
+            crossword
+                .words
+                .iter()
+                .map(|word| JsonSolvedWord {
+                    word: word.str.to_string(),
+                    placements: word
+                        .placements
+                        .iter()
+                        .map(|placement| JsonPlacement {
+                            start: placement.start,
+                            end: placement.end(),
+                            direction: placement.direction,
+                        })
+                        .collect(),
+                })
+                .collect()
+        });
+
+        Self {
+            headers: crossword
+                .headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            width: crossword.width,
+            height: crossword.height,
+            grid: crossword
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.grapheme.as_str()).collect())
+                .collect(),
+            words: crossword
+                .words
+                .iter()
+                .map(|word| word.str.to_string())
+                .collect(),
+            solution,
+        }
+    }
+}
+
+impl Crossword<'_> {
+    /// Writes the crossword and, if it has been solved, where each word was found as JSON.
+    pub fn to_json(&self) -> String {
+        // Serializing strings, numbers and enums cannot fail
+        serde_json::to_string_pretty(&JsonCrossword::from(self)).unwrap()
+    }
+}
+
+/// Headers are written as a JSON object but kept in order.
+mod headers {
+    use serde::{
+        de::{MapAccess, Visitor},
+        Deserializer, Serializer,
+    };
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        headers: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(headers.iter().map(|(key, value)| (key, value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        struct HeadersVisitor;
+
+        impl<'de> Visitor<'de> for HeadersVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an object of strings")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut headers = Vec::new();
+                while let Some(header) = map.next_entry()? {
+                    headers.push(header);
+                }
+                Ok(headers)
+            }
+        }
+
+        deserializer.deserialize_map(HeadersVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_json() {
+        // Quotes and backslashes need escaping in JSON strings, and the phrase keeps its space
+        let mut crossword = Crossword::try_from(indoc! {
+            r#"title: Say "hi" \o/
+
+               h a b
+               i x y
+               hi "x y""#
+        })
+        .unwrap();
+        crossword.solve(false, &Direction::ALL, false);
+
+        assert_eq!(
+            crossword.to_json(),
+            indoc! {r#"
+                {
+                  "headers": {
+                    "title": "Say \"hi\" \\o/"
+                  },
+                  "width": 3,
+                  "height": 2,
+                  "grid": [
+                    "hab",
+                    "ixy"
+                  ],
+                  "words": [
+                    "hi",
+                    "x y"
+                  ],
+                  "solution": [
+                    {
+                      "word": "hi",
+                      "placements": [
+                        {
+                          "start": {
+                            "x": 0,
+                            "y": 0
+                          },
+                          "end": {
+                            "x": 0,
+                            "y": 1
+                          },
+                          "direction": "south"
+                        }
+                      ]
+                    },
+                    {
+                      "word": "x y",
+                      "placements": [
+                        {
+                          "start": {
+                            "x": 1,
+                            "y": 1
+                          },
+                          "end": {
+                            "x": 2,
+                            "y": 1
+                          },
+                          "direction": "east"
+                        }
+                      ]
+                    }
+                  ]
+                }"#
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        // Headers keep their order, including ones JSON has no special place for
+        let crossword = Crossword::try_from(indoc! {
+            "language: Japanese
+             title: カタカナ
+             case: insensitive
+
+             ク ロ ス
+             ワ ー ド
+             クロス ワード"
+        })
+        .unwrap();
+
+        assert_eq!(
+            Crossword::from_json(&crossword.to_json(), None),
+            Ok(crossword)
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use super::Grapheme;
+use std::borrow::Cow;
+use unicode_normalization::{char::is_combining_mark, is_nfc, is_nfkc, UnicodeNormalization};
+
+/// How the words are compared with the grid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Matching {
+    /// Whether uppercase and lowercase letters match each other.
+    pub ignore_case: bool,
+    /// The normal form the grid and the words are converted to before they are parsed.
+    pub normalization: Option<Normalization>,
+    /// How graphemes are folded before they are compared, such as to ignore diacritics.
+    pub folding: Option<Folding>,
+}
+
+impl Matching {
+    pub fn graphemes_match(&self, grid_grapheme: &str, word_grapheme: &str) -> bool {
+        grid_grapheme == word_grapheme
+            || (self.ignore_case || self.folding.is_some())
+                && self.fold(grid_grapheme) == self.fold(word_grapheme)
+    }
+
+    /// Converts the grapheme to the form it is compared in.
+    ///
+    /// Case is folded first so that a folding table only needs to list lowercase letters.
+    fn fold<'g>(&self, grapheme: &'g str) -> Cow<'g, str> {
+        let grapheme = self.fold_case(grapheme);
+        match &self.folding {
+            None => grapheme,
+            Some(Folding::Diacritics) => Cow::Owned(
+                grapheme
+                    .nfd()
+                    .filter(|&char| !is_combining_mark(char))
+                    .nfc()
+                    .collect(),
+            ),
+            Some(Folding::Table(table)) => table
+                .iter()
+                .find(|(from, _)| self.fold_case(from.as_str()) == grapheme)
+                .map_or(grapheme, |(_, to)| {
+                    Cow::Owned(self.fold_case(to.as_str()).into_owned())
+                }),
+        }
+    }
+
+    fn fold_case<'g>(&self, grapheme: &'g str) -> Cow<'g, str> {
+        if self.ignore_case {
+            Cow::Owned(grapheme.to_lowercase())
+        } else {
+            Cow::Borrowed(grapheme)
+        }
+    }
+}
+
+/// How graphemes are made to match each other even though they are written differently.
+///
+/// The grid keeps its original characters, so found words are still highlighted as they are written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Folding {
+    /// Strip diacritics, so that for example `ä` and `a` match.
+    Diacritics,
+    /// Treat the first grapheme of every pair like the second one, such as `ä=a, ö=o` for German learners.
+    Table(Vec<(Grapheme, Grapheme)>),
+}
+
+/// A Unicode normal form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    /// Canonical composition, which turns for example `a` followed by a combining ring into `å`.
+    Nfc,
+    /// Compatibility composition, which also turns for example the full-width `Ａ` into `A`.
+    Nfkc,
+}
+
+impl Normalization {
+    /// Converts the text to this normal form, borrowing it if it already is in this form.
+    pub fn normalize(self, str: &str) -> Cow<'_, str> {
+        match self {
+            Normalization::Nfc if is_nfc(str) => Cow::Borrowed(str),
+            Normalization::Nfc => Cow::Owned(str.nfc().collect()),
+            Normalization::Nfkc if is_nfkc(str) => Cow::Borrowed(str),
+            Normalization::Nfkc => Cow::Owned(str.nfkc().collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching() {
+        let exact = Matching::default();
+        let ignore_case = Matching {
+            ignore_case: true,
+            ..Matching::default()
+        };
+
+        assert!(exact.graphemes_match("å", "å"));
+        assert!(!exact.graphemes_match("Å", "å"));
+        assert!(ignore_case.graphemes_match("Å", "å"));
+        assert!(ignore_case.graphemes_match("A\u{30a}", "a\u{30a}"));
+        assert!(!ignore_case.graphemes_match("a", "b"));
+
+        let diacritics = Matching {
+            folding: Some(Folding::Diacritics),
+            ..Matching::default()
+        };
+        assert!(diacritics.graphemes_match("ä", "a"));
+        assert!(diacritics.graphemes_match("u\u{308}", "u"));
+        assert!(!diacritics.graphemes_match("Ü", "u"));
+        assert!(!diacritics.graphemes_match("ø", "o"));
+
+        let table = Matching {
+            ignore_case: true,
+            folding: Some(Folding::Table(vec![("ä".into(), "a".into())])),
+            ..Matching::default()
+        };
+        assert!(table.graphemes_match("Ä", "a"));
+        assert!(!table.graphemes_match("ö", "o"));
+
+        assert_eq!(Normalization::Nfc.normalize("a\u{30a}"), "å");
+        assert!(matches!(
+            Normalization::Nfc.normalize("å"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(Normalization::Nfc.normalize("Ａ"), "Ａ");
+        assert_eq!(Normalization::Nfkc.normalize("Ａ"), "A");
+    }
+}
@@ -1,75 +1,603 @@
-use super::{Cell, Crossword, Word};
+use super::{
+    graphemes, Cell, Crossword, Direction, Fill, Folding, Grapheme, Matching, Normalization, Word,
+    ENGLISH, GERMAN, SWEDISH,
+};
 use crate::Error;
+use std::borrow::Cow;
 
-impl<'a> TryFrom<&'a str> for Crossword<'a> {
+impl TryFrom<&str> for Direction {
+    type Error = Error;
+
+    fn try_from(direction_str: &str) -> Result<Self, Self::Error> {
+        match direction_str {
+            "north" | "n" => Ok(Direction::North),
+            "northeast" | "ne" => Ok(Direction::Northeast),
+            "east" | "e" => Ok(Direction::East),
+            "southeast" | "se" => Ok(Direction::Southeast),
+            "south" | "s" => Ok(Direction::South),
+            "southwest" | "sw" => Ok(Direction::Southwest),
+            "west" | "w" => Ok(Direction::West),
+            "northwest" | "nw" => Ok(Direction::Northwest),
+            _ => Err("invalid direction"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Fill {
+    type Error = Error;
+
+    fn try_from(fill_str: &str) -> Result<Self, Self::Error> {
+        match fill_str {
+            "words" => Ok(Fill::Words),
+            "letters" => Ok(Fill::Letters),
+            "english" => Ok(Fill::Frequencies(ENGLISH)),
+            "german" => Ok(Fill::Frequencies(GERMAN)),
+            "swedish" => Ok(Fill::Frequencies(SWEDISH)),
+            _ => {
+                if let Some(alphabet) = fill_str.strip_prefix("alphabet:") {
+                    Ok(Fill::Alphabet(
+                        graphemes(alphabet).map(Grapheme::from).collect(),
+                    ))
+                } else {
+                    Err("invalid fill")
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<&str> for Normalization {
     type Error = Error;
 
+    fn try_from(normalization_str: &str) -> Result<Self, Self::Error> {
+        match normalization_str {
+            "nfc" => Ok(Normalization::Nfc),
+            "nfkc" => Ok(Normalization::Nfkc),
+            _ => Err("invalid normalization"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Folding {
+    type Error = Error;
+
+    /// Parses `diacritics` or a comma-separated table of pairs of graphemes such as `ä=a, ö=o`.
+    fn try_from(folding_str: &str) -> Result<Self, Self::Error> {
+        if folding_str == "diacritics" {
+            return Ok(Folding::Diacritics);
+        }
+
+        let single_grapheme = |str: &str| {
+            let mut graphemes = graphemes(str.trim());
+            graphemes
+                .next()
+                .filter(|_| graphemes.next().is_none())
+                .map(Grapheme::from)
+        };
+        folding_str
+            .split(',')
+            .map(|pair| {
+                let (from, to) = pair.split_once('=')?;
+                Some((single_grapheme(from)?, single_grapheme(to)?))
+            })
+            .collect::<Option<_>>()
+            .map(Folding::Table)
+            .ok_or("invalid folding")
+    }
+}
+
+/// An error in the text form of a crossword.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// There are no rows in the grid.
+    EmptyGrid,
+    /// There is nothing after the `---` line, which is supposed to be followed by the word list.
+    NoWords { line: usize },
+    /// The lines after this blank line could be either rows of the grid or the word list.
+    AmbiguousWordList { line: usize },
+    /// A row of the grid has a different width than the first row.
+    ///
+    /// `line` and `column` are 1-based and `column` counts characters.
+    /// The column points at the first character too many or at the end of a line that is too short.
+    InconsistentWidth {
+        line: usize,
+        column: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A phrase in the word list has an opening double quote but no closing one.
+    ///
+    /// `line` and `column` are 1-based and `column` counts characters and points at the opening quote.
+    UnclosedQuote { line: usize, column: usize },
+    /// A header that changes how the crossword is solved has an invalid value.
+    InvalidHeader { key: String, line: Option<usize> },
+    /// The JSON form of the crossword is invalid.
+    #[cfg(feature = "json")]
+    Json {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+}
+
+impl ParseError {
+    /// The 1-based line the error is on, if it is on a specific line.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::EmptyGrid => None,
+            ParseError::NoWords { line }
+            | ParseError::AmbiguousWordList { line }
+            | ParseError::InconsistentWidth { line, .. }
+            | ParseError::UnclosedQuote { line, .. } => Some(*line),
+            ParseError::InvalidHeader { line, .. } => *line,
+            #[cfg(feature = "json")]
+            ParseError::Json { line, .. } => *line,
+        }
+    }
+
+    /// The 1-based column the error is at, if it is at a specific column.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ParseError::InconsistentWidth { column, .. }
+            | ParseError::UnclosedQuote { column, .. } => Some(*column),
+            #[cfg(feature = "json")]
+            ParseError::Json { column, .. } => *column,
+            _ => None,
+        }
+    }
+}
+
+/// Whether this line is a comment, which starts with `#` in the first column.
+///
+/// A first row of the grid that starts with `#` can be indented to keep it from being read as a comment.
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#')
+}
+
+/// Parses a `key: value` header line.
+///
+/// The key consists of alphanumeric characters, `-` and `_` and the colon must be followed by whitespace or end the line.
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.trim().split_once(':')?;
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '-' || char == '_')
+        || !(value.is_empty() || value.starts_with(char::is_whitespace))
+    {
+        return None;
+    }
+
+    Some((key, value.trim()))
+}
+
+/// Splits a row of the grid into the graphemes of its cells along with the 0-based column they start at.
+///
+/// Columns count characters. Whitespace between the cells is skipped.
+fn row_cells(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut column = 0;
+    graphemes(line).filter_map(move |grapheme| {
+        let start = column;
+        column += grapheme.chars().count();
+        (!grapheme.chars().all(char::is_whitespace)).then_some((start, grapheme))
+    })
+}
+
+/// Splits a line of a word list into words, keeping phrases in double quotes such as `"ICE CREAM"` together.
+///
+/// If a quote is not closed, the 0-based column of the quote is returned, counting characters.
+pub fn split_words(line: &str) -> Result<Vec<&str>, usize> {
+    let mut words = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (word, after) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or_else(|| line[..line.len() - rest.len()].chars().count())?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()))
+        };
+        words.push(word);
+        rest = after.trim_start();
+    }
+    Ok(words)
+}
+
+/// Parses a comma-separated list of directions such as `east, south`.
+pub fn split_directions(directions_str: &str) -> Result<Vec<Direction>, Error> {
+    directions_str
+        .split(',')
+        .map(|direction| Direction::try_from(direction.trim()))
+        .collect()
+}
+
+/// Reads the `directions` header, which is given as key, value and line number like in [`parse_matching`].
+fn parse_directions<'h>(
+    headers: impl IntoIterator<Item = (&'h str, &'h str, Option<usize>)>,
+) -> Result<Vec<Direction>, ParseError> {
+    let mut directions = Direction::ALL.to_vec();
+    for (key, value, line) in headers {
+        if key == "directions" {
+            directions = split_directions(value).map_err(|_| ParseError::InvalidHeader {
+                key: key.to_string(),
+                line,
+            })?;
+        }
+    }
+    Ok(directions)
+}
+
+/// Reads the `case`, `normalization` and `fold` headers, which are given as key, value and line number.
+fn parse_matching<'h>(
+    headers: impl IntoIterator<Item = (&'h str, &'h str, Option<usize>)>,
+) -> Result<Matching, ParseError> {
+    let mut matching = Matching::default();
+    for (key, value, line) in headers {
+        let invalid = || ParseError::InvalidHeader {
+            key: key.to_string(),
+            line,
+        };
+        match key {
+            "case" => {
+                matching.ignore_case = match value {
+                    "sensitive" => false,
+                    "insensitive" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            "normalization" => {
+                matching.normalization = match value {
+                    "none" => None,
+                    _ => Some(Normalization::try_from(value).map_err(|_| invalid())?),
+                }
+            }
+            "fold" => {
+                matching.folding = match value {
+                    "none" => None,
+                    _ => Some(Folding::try_from(value).map_err(|_| invalid())?),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(matching)
+}
+
+impl<'a> TryFrom<&'a str> for Crossword<'a> {
+    type Error = ParseError;
+
     fn try_from(crossword_str: &'a str) -> Result<Self, Self::Error> {
-        let mut lines = crossword_str.lines().peekable();
+        Crossword::parse(crossword_str, None)
+    }
+}
+
+impl<'a> Crossword<'a> {
+    /// Parses the text form of a crossword.
+    ///
+    /// `normalization` takes the place of the `normalization` header if it is `Some`, where `Some(None)` turns
+    /// normalization off. If a normalization is used, the whole text is normalized and parsed again before its grid
+    /// is looked at.
+    pub fn parse(
+        crossword_str: &'a str,
+        normalization: Option<Option<Normalization>>,
+    ) -> Result<Self, ParseError> {
+        let is_blank = |&(line, _): &(&str, usize)| line.trim().is_empty();
+
+        let mut lines = crossword_str.lines().zip(1..).collect::<Vec<_>>();
+
+        // Headers and comments come before the grid.
+        // Comments are not allowed after that because a row of the grid may start with `#`.
+        let mut headers = Vec::new();
+        let mut header_lines = Vec::new();
+        let mut start = 0;
+        for line in &lines {
+            if is_blank(line) || is_comment(line.0) {
+                start += 1;
+            } else if let Some((key, value)) = parse_header(line.0) {
+                headers.push((key.into(), value.into()));
+                header_lines.push(line.1);
+                start += 1;
+            } else {
+                break;
+            }
+        }
+        lines.drain(..start);
+
+        let header_items = || {
+            headers.iter().zip(header_lines.iter().copied()).map(
+                |((key, value), line): (&(Cow<str>, Cow<str>), _)| {
+                    (key.as_ref(), value.as_ref(), Some(line))
+                },
+            )
+        };
+        let mut matching = parse_matching(header_items())?;
+        let directions = parse_directions(header_items())?;
+        if let Some(normalization) = normalization {
+            matching.normalization = normalization;
+        }
+        // Once normalized, the text is borrowed when parsing it again
+        if let Some(normalization) = matching.normalization {
+            if let Cow::Owned(normalized) = normalization.normalize(crossword_str) {
+                return Crossword::parse(&normalized, Some(Some(normalization)))
+                    .map(Crossword::into_owned);
+            }
+        }
+
+        // Leading and trailing blank lines don't mean anything
+        let start = lines.iter().position(|line| !is_blank(line)).unwrap_or(0);
+        let end = lines
+            .iter()
+            .rposition(|line| !is_blank(line))
+            .map_or(0, |last| last + 1);
+        let lines = &lines[start..end];
+
+        // The word list comes after a `---` line, after the last blank line or on the last line.
+        // Blank lines may also separate the rows of the grid, in which case the lines after the last one
+        // are the last row followed by the word list. The lines after a blank line are the word list if they cannot
+        // be rows, which is if there is only one of them or they differ in width. They are the last row and the word
+        // list if every row before them is on its own between blank lines. Anything else needs a `---` line.
+        let first_width = lines.first().map_or(0, |(line, _)| row_cells(line).count());
+        let could_be_rows = |lines: &[(&str, usize)]| {
+            lines.len() > 1
+                && lines[..lines.len() - 1]
+                    .iter()
+                    .all(|(line, _)| row_cells(line).count() == first_width)
+        };
+        let rows_between_blank_lines = |lines: &[(&str, usize)]| {
+            lines.iter().filter(|line| !is_blank(line)).count() > 1
+                && lines
+                    .windows(2)
+                    .all(|pair| is_blank(&pair[0]) || is_blank(&pair[1]))
+        };
+        let (grid_lines, word_lines) =
+            if let Some(separator) = lines.iter().position(|&(line, _)| line.trim() == "---") {
+                if separator + 1 == lines.len() {
+                    return Err(ParseError::NoWords {
+                        line: lines[separator].1,
+                    });
+                }
+                (&lines[..separator], &lines[separator + 1..])
+            } else if let Some(blank) = lines.iter().rposition(is_blank) {
+                let (before, after) = (&lines[..blank], &lines[blank + 1..]);
+                if !could_be_rows(after) {
+                    (before, after)
+                } else if after.len() == 2 && rows_between_blank_lines(before) {
+                    lines.split_at(lines.len() - 1)
+                } else {
+                    return Err(ParseError::AmbiguousWordList {
+                        line: lines[blank].1,
+                    });
+                }
+            } else {
+                lines.split_at(lines.len().saturating_sub(1))
+            };
 
-        // Get the length of the first line to use as the initial capacity of `cells`
+        // Ignore lines only containing whitespace
+        let mut grid_lines = grid_lines.iter().filter(|line| !is_blank(line)).peekable();
+
+        // Get the length of the first row to use as the initial capacity of `cells`
         // and to check for line width inconsistencies
-        let width = if let Some(first_line) = lines.peek() {
-            first_line
-                .chars()
-                .filter(|char| !char.is_whitespace())
-                .count()
+        let width = if let Some((first_line, _)) = grid_lines.peek() {
+            row_cells(first_line).count()
         } else {
-            return Err("empty grid");
+            return Err(ParseError::EmptyGrid);
         };
 
         // Together with `with_capacity` and `reserve_exact` (see below) we can make sure that
         // we end up with the perfect capacity
         let mut cells = Vec::<Cell>::with_capacity(width);
-        let mut height = None;
-        let mut words = Vec::<Word>::new();
-
-        while let Some(line) = lines.next() {
-            // Is this the last line?
-            if lines.peek().is_none() {
-                // Parse the word list
-                for word in line.split_whitespace() {
-                    words.push(Word::new(word.into()));
-                }
-                if words.is_empty() {
-                    return Err("empty grid");
-                }
-            } else {
-                // Ignore lines only containing whitespace
-                if line.trim().is_empty() {
-                    continue;
-                }
+        let mut height = 0;
 
-                // In the common case, we know exactly how much more we need to allocate
-                cells.reserve_exact(width);
+        for &(line, line_number) in grid_lines {
+            let found = row_cells(line).count();
+            if found != width {
+                let column = row_cells(line)
+                    .nth(width)
+                    .map_or(line.chars().count(), |(column, _)| column);
 
-                for char in line.chars().filter(|char| !char.is_whitespace()) {
-                    cells.push(Cell::new(char));
-                }
+                return Err(ParseError::InconsistentWidth {
+                    line: line_number,
+                    column: column + 1,
+                    expected: width,
+                    found,
+                });
+            }
 
-                // If the length is not the same as what we allocated,
-                // we know that this line has an inconsistent width
-                if cells.len() != cells.capacity() {
-                    return Err("inconsistent width");
-                }
+            // In the common case, we know exactly how much more we need to allocate
+            cells.reserve_exact(width);
 
-                *height.get_or_insert(0) += 1;
+            for (_, grapheme) in row_cells(line) {
+                cells.push(Cell::new(grapheme));
             }
+
+            height += 1;
         }
 
-        if let Some(height) = height {
-            let solved = false;
+        let mut words = Vec::new();
+        for &(line, line_number) in word_lines {
+            let line_words = split_words(line).map_err(|column| ParseError::UnclosedQuote {
+                line: line_number,
+                column: column + 1,
+            })?;
+            words.extend(
+                line_words
+                    .into_iter()
+                    .map(|word| Word::new(word.into()))
+                    .filter(|word| !word.is_empty()),
+            );
+        }
+        let solved = false;
 
-            Ok(Crossword {
-                cells,
-                width,
-                height,
-                words,
-                solved,
+        Ok(Crossword {
+            headers,
+            cells,
+            width,
+            height,
+            words,
+            solved,
+            matching,
+            directions,
+        })
+    }
+}
+
+/// Converts a JSON error into a parsing error that reports its location separately.
+#[cfg(feature = "json")]
+fn json_error(err: serde_json::Error) -> ParseError {
+    let message = err.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+    ParseError::Json {
+        message: message.to_string(),
+        line: Some(err.line()).filter(|&line| line != 0),
+        column: Some(err.column()).filter(|&column| column != 0),
+    }
+}
+
+#[cfg(feature = "json")]
+impl Crossword<'static> {
+    /// Parses the JSON form of a crossword as written by [`Crossword::to_json`].
+    ///
+    /// The solution, if any, is ignored. `normalization` works like in [`Crossword::parse`].
+    pub fn from_json(
+        json_str: &str,
+        normalization: Option<Option<Normalization>>,
+    ) -> Result<Self, ParseError> {
+        let json: super::json::JsonCrossword =
+            serde_json::from_str(json_str).map_err(json_error)?;
+
+        let rows = json
+            .grid
+            .iter()
+            .map(|row| {
+                row_cells(row)
+                    .map(|(_, grapheme)| Grapheme::from(grapheme))
+                    .collect()
             })
-        } else {
-            Err("empty grid")
+            .collect();
+
+        Self::from_rows(json.headers, rows, json.words, normalization)
+    }
+
+    /// Parses an ipuz word search, which may be wrapped in `ipuz(...)`.
+    ///
+    /// `normalization` works like in [`Crossword::parse`].
+    pub fn from_ipuz(
+        ipuz_str: &str,
+        normalization: Option<Option<Normalization>>,
+    ) -> Result<Self, ParseError> {
+        let invalid = |message: String| ParseError::Json {
+            message,
+            line: None,
+            column: None,
+        };
+
+        let trimmed = ipuz_str.trim();
+        let ipuz_str = trimmed
+            .strip_prefix("ipuz(")
+            .and_then(|ipuz_str| ipuz_str.strip_suffix(')'))
+            .unwrap_or(ipuz_str);
+        let ipuz: super::ipuz::IpuzInput = serde_json::from_str(ipuz_str).map_err(json_error)?;
+
+        if !ipuz.is_word_search() {
+            return Err(invalid("not an ipuz word search".to_string()));
+        }
+
+        let mut rows = Vec::with_capacity(ipuz.puzzle.len());
+        for (y, row) in ipuz.puzzle.iter().enumerate() {
+            let row = row
+                .iter()
+                .enumerate()
+                .map(|(x, cell)| {
+                    cell.grapheme().ok_or_else(|| {
+                        invalid(format!(
+                            "expected a single character in cell ({}, {})",
+                            x, y
+                        ))
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            rows.push(row);
+        }
+
+        let headers = ipuz.headers();
+        Self::from_rows(headers, rows, ipuz.solution.into_words(), normalization)
+    }
+
+    /// Builds a crossword out of rows of cells from a format without line information.
+    fn from_rows(
+        headers: Vec<(String, String)>,
+        rows: Vec<Vec<Grapheme>>,
+        words: Vec<String>,
+        normalization: Option<Option<Normalization>>,
+    ) -> Result<Self, ParseError> {
+        let mut matching = parse_matching(
+            headers
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str(), None)),
+        )?;
+        if let Some(normalization) = normalization {
+            matching.normalization = normalization;
+        }
+        let directions = parse_directions(
+            headers
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str(), None)),
+        )?;
+        let normalize = |str: String| match matching.normalization {
+            Some(normalization) => normalization.normalize(&str).into_owned(),
+            None => str,
+        };
+        // Characters may be combined, so every row is normalized as a whole
+        let rows: Vec<Vec<Grapheme>> = rows
+            .into_iter()
+            .map(|row| {
+                let row = row.iter().map(Grapheme::as_str).collect();
+                graphemes(&normalize(row)).map(Grapheme::from).collect()
+            })
+            .collect();
+
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(ParseError::EmptyGrid);
         }
+
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(ParseError::Json {
+                    message: format!(
+                        "inconsistent width: expected {} characters like the first row but found {} in row {}",
+                        width,
+                        row.len(),
+                        y + 1
+                    ),
+                    line: None,
+                    column: None,
+                });
+            }
+            cells.extend(row.iter().cloned().map(Cell::new));
+        }
+
+        Ok(Crossword {
+            headers: headers
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            cells,
+            width,
+            height: rows.len(),
+            words: words
+                .into_iter()
+                .map(|word| Word::new(normalize(word).into()))
+                .filter(|word| !word.is_empty())
+                .collect(),
+            solved: false,
+            matching,
+            directions,
+        })
     }
 }
 
@@ -79,32 +607,141 @@ mod tests {
     use crate::util::Point;
     use indoc::indoc;
 
+    #[test]
+    fn test_direction_try_from() {
+        assert_eq!(Direction::try_from("north"), Ok(Direction::North));
+        assert_eq!(Direction::try_from("sw"), Ok(Direction::Southwest));
+        assert_eq!(Direction::try_from("up"), Err("invalid direction"));
+        assert!(Direction::ALL.iter().all(|&direction| Direction::try_from(
+            direction.to_string().as_ref()
+        ) == Ok(direction)));
+    }
+
+    #[test]
+    fn test_fill_try_from() {
+        assert_eq!(Fill::try_from("words"), Ok(Fill::Words));
+        assert_eq!(Fill::try_from("swedish"), Ok(Fill::Frequencies(SWEDISH)));
+        assert_eq!(
+            Fill::try_from("alphabet:ABC"),
+            Ok(Fill::Alphabet(vec!['A'.into(), 'B'.into(), 'C'.into()]))
+        );
+        assert_eq!(Fill::try_from("klingon"), Err("invalid fill"));
+    }
+
+    #[test]
+    fn test_folding_try_from() {
+        assert_eq!(Folding::try_from("diacritics"), Ok(Folding::Diacritics));
+        assert_eq!(
+            Folding::try_from("ä=a, ü = u"),
+            Ok(Folding::Table(vec![
+                ("ä".into(), "a".into()),
+                ("ü".into(), "u".into())
+            ]))
+        );
+        assert_eq!(Folding::try_from("ä=ae"), Err("invalid folding"));
+        assert_eq!(Folding::try_from("ä"), Err("invalid folding"));
+    }
+
     #[test]
     fn test_try_from_empty_grid() {
-        assert!(matches!(Crossword::try_from(""), Err("empty grid")));
-        assert!(matches!(Crossword::try_from("a"), Err("empty grid")));
-        assert!(matches!(Crossword::try_from("\n"), Err("empty grid")));
-        assert!(matches!(Crossword::try_from("\na"), Err("empty grid")));
+        assert_eq!(Crossword::try_from(""), Err(ParseError::EmptyGrid));
+        assert_eq!(Crossword::try_from("a"), Err(ParseError::EmptyGrid));
+        assert_eq!(Crossword::try_from("\n"), Err(ParseError::EmptyGrid));
+        assert_eq!(Crossword::try_from("\na"), Err(ParseError::EmptyGrid));
+        assert_eq!(Crossword::try_from("---\na"), Err(ParseError::EmptyGrid));
+        assert_eq!(
+            Crossword::try_from("a\n---\n\n"),
+            Err(ParseError::NoWords { line: 2 })
+        );
     }
 
     #[test]
     fn test_try_from_inconsistent_width() {
-        assert!(matches!(
+        assert_eq!(
             Crossword::try_from(indoc! {
                 "aaa
                  aaaaaa
                  aaaaaa"
             }),
-            Err("inconsistent width")
-        ));
-        assert!(matches!(
+            Err(ParseError::InconsistentWidth {
+                line: 2,
+                column: 4,
+                expected: 3,
+                found: 6
+            })
+        );
+        assert_eq!(
             Crossword::try_from(indoc! {
-                "aaaaaa
-                 aaa
-                 aaaaaa"
+                "a a a a a a
+                 a a a
+                 a a a a a a"
             }),
-            Err("inconsistent width")
-        ));
+            Err(ParseError::InconsistentWidth {
+                line: 2,
+                column: 6,
+                expected: 6,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_from_graphemes() {
+        let crossword = Crossword::try_from(indoc! {
+            "a\u{30a} b 👍🏽
+             क्ष x y
+             a\u{30a}b 👍🏽क्ष"
+        })
+        .unwrap();
+
+        assert_eq!(crossword.width, 3);
+        assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new("a\u{30a}"));
+        assert_eq!(crossword[Point { x: 2, y: 0 }], Cell::new("👍🏽"));
+        assert_eq!(crossword[Point { x: 0, y: 1 }], Cell::new("क्ष"));
+        assert_eq!(crossword.words[0].len, 2);
+        assert_eq!(crossword.words[1].len, 2);
+
+        // The column counts characters, so it points at the start of the extra grapheme
+        assert_eq!(
+            Crossword::try_from("a\u{30a}bc\nabcd\nabc"),
+            Err(ParseError::InconsistentWidth {
+                line: 2,
+                column: 4,
+                expected: 3,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("  red blue "), Ok(vec!["red", "blue"]));
+        assert_eq!(
+            split_words(r#"T-SHIRT "ICE CREAM"  "rock 'n' roll""#),
+            Ok(vec!["T-SHIRT", "ICE CREAM", "rock 'n' roll"])
+        );
+        assert_eq!(split_words(r#"blå "ICE CREAM"#), Err(4));
+    }
+
+    #[test]
+    fn test_try_from_phrases() {
+        let crossword = Crossword::try_from(indoc! {
+            r#"I C E C R E A M
+               T S H I R T X X
+               "ICE CREAM" T-SHIRT -"#
+        })
+        .unwrap();
+
+        assert_eq!(crossword.words.len(), 2);
+        assert_eq!(crossword.words[0].str, "ICE CREAM");
+        assert_eq!(crossword.words[0].grid_str, "ICECREAM");
+        assert_eq!(crossword.words[0].len, 8);
+        assert_eq!(crossword.words[1].grid_str, "TSHIRT");
+
+        assert_eq!(
+            Crossword::try_from("abc\nabc\n\nabc \"a b"),
+            Err(ParseError::UnclosedQuote { line: 4, column: 5 })
+        );
     }
 
     #[test]
@@ -142,33 +779,288 @@ mod tests {
             .all(|crossword| *crossword == crosswords[0]));
     }
 
+    #[test]
+    fn test_try_from_word_sections() {
+        let crossword1 = Crossword::try_from(indoc! {
+            "abc
+             def
+             ab cd ef"
+        })
+        .unwrap();
+        let crossword2 = Crossword::try_from(indoc! {
+            "abc
+             def
+
+             ab
+             cd ef
+
+             "
+        })
+        .unwrap();
+        let crossword3 = Crossword::try_from(indoc! {
+            "abc
+
+             def
+             ---
+             ab
+
+             cd
+             ef"
+        })
+        .unwrap();
+
+        let crosswords = [crossword1, crossword2, crossword3];
+
+        assert_eq!(crosswords[0].height, 2);
+        assert_eq!(crosswords[0].words.len(), 3);
+        assert!(crosswords
+            .iter()
+            .all(|crossword| *crossword == crosswords[0]));
+    }
+
+    #[test]
+    fn test_try_from_blank_lines_between_rows() {
+        // The lines after the last blank line are the last row and the word list, not a word list of two lines
+        let crossword = Crossword::try_from("a   a   a\n\na   a   a\n\na   a   a\naaa").unwrap();
+
+        assert_eq!(crossword.width, 3);
+        assert_eq!(crossword.height, 3);
+        assert_eq!(crossword.words, vec![Word::new("aaa".into())]);
+
+        // A single line after the last blank line is the word list either way
+        let crossword = Crossword::try_from("a a\n\na a\n\naa a").unwrap();
+        assert_eq!(crossword.height, 2);
+        assert_eq!(crossword.words.len(), 2);
+
+        // These lines could be the last row and the word list or a word list of two lines
+        assert_eq!(
+            Crossword::try_from("cat\ndog\n\ncat\ndog"),
+            Err(ParseError::AmbiguousWordList { line: 3 })
+        );
+        let crossword = Crossword::try_from("cat\ndog\n---\ncat\ndog").unwrap();
+        assert_eq!(crossword.height, 2);
+        assert_eq!(
+            crossword.words,
+            vec![Word::new("cat".into()), Word::new("dog".into())]
+        );
+    }
+
+    #[test]
+    fn test_try_from_headers_and_comments() {
+        let crossword = Crossword::try_from(indoc! {
+            "# A crossword with a title
+             title: Colors
+             author: Jane Doe
+             difficulty:
+
+             # The grid
+             r e d
+             a:b
+             red"
+        })
+        .unwrap();
+
+        assert_eq!(
+            crossword.headers,
+            vec![
+                ("title".into(), "Colors".into()),
+                ("author".into(), "Jane Doe".into()),
+                ("difficulty".into(), "".into())
+            ]
+        );
+        assert_eq!(crossword.height, 2);
+        assert_eq!(crossword[Point { x: 1, y: 1 }], Cell::new(':'));
+        assert_eq!(crossword.words, vec![Word::new("red".into())]);
+
+        // After the headers, lines starting with `#` are part of the grid or the word list
+        let crossword = Crossword::try_from("# Comment\n\n # a b\n# d e\n#ab").unwrap();
+        assert_eq!(crossword.height, 2);
+        assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('#'));
+        assert_eq!(crossword.words, vec![Word::new("#ab".into())]);
+    }
+
+    #[test]
+    fn test_try_from_matching_headers() {
+        // The grid is decomposed, so it is only 3 characters wide after normalization
+        let crossword = Crossword::try_from(
+            "case: insensitive\nnormalization: nfc\n\na\u{30a} b c\nx y z\n\nA\u{30a}BC",
+        )
+        .unwrap();
+
+        assert_eq!(
+            crossword.matching,
+            Matching {
+                ignore_case: true,
+                normalization: Some(Normalization::Nfc),
+                folding: None
+            }
+        );
+        assert_eq!(crossword.width, 3);
+        assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('å'));
+        assert_eq!(crossword.words, vec![Word::new("ÅBC".into())]);
+
+        assert_eq!(
+            Crossword::try_from("title: Colors\ncase: upper\n\nred\nred"),
+            Err(ParseError::InvalidHeader {
+                key: "case".into(),
+                line: Some(2)
+            })
+        );
+        assert_eq!(
+            Crossword::try_from("fold: diacritics\n\nred\nred")
+                .unwrap()
+                .matching
+                .folding,
+            Some(Folding::Diacritics)
+        );
+        assert_eq!(
+            Crossword::try_from("normalization: nfd\n\nred\nred"),
+            Err(ParseError::InvalidHeader {
+                key: "normalization".into(),
+                line: Some(1)
+            })
+        );
+
+        // A normalization given to `parse` replaces the header before any text is normalized
+        let crossword_str = "normalization: nfkc\n\nＡb\ncd\n\nＡb";
+        let crossword = Crossword::try_from(crossword_str).unwrap();
+        assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('A'));
+        for normalization in [Some(Normalization::Nfc), None] {
+            let crossword = Crossword::parse(crossword_str, Some(normalization)).unwrap();
+            assert_eq!(crossword.matching.normalization, normalization);
+            assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('Ａ'));
+            assert_eq!(crossword.words, vec![Word::new("Ａb".into())]);
+        }
+    }
+
+    #[test]
+    fn test_try_from_directions_header() {
+        let crossword = Crossword::try_from("abc\ndef\n\nbe").unwrap();
+        assert_eq!(crossword.directions(), Direction::ALL);
+
+        let crossword =
+            Crossword::try_from("title: Downhill\ndirections: south,  se\n\nabc\ndef\n\nbe")
+                .unwrap();
+        assert_eq!(
+            crossword.directions(),
+            [Direction::South, Direction::Southeast]
+        );
+
+        assert_eq!(
+            Crossword::try_from("title: Downhill\ndirections: south, up\n\nabc\ndef\n\nbe"),
+            Err(ParseError::InvalidHeader {
+                key: "directions".into(),
+                line: Some(2)
+            })
+        );
+    }
+
     #[test]
     fn test_try_from() {
-        //let crossword = Crossword::try_from(indoc! {
-        //    "0K000000000000000000
-        //     0o000000000000000000
-        //     Crossword00000000000
-        //     0s0000クロスワード00000000
-        //     0o000000000000000000
-        //     0r00填字遊戲000000000000
-        //     0d000000000000000000
-        //     Korsord Crossword クロスワード Kreuzworträtsel 填字遊戲"
-        //})
-        //.unwrap();
-
-        //assert!(!crossword.solved);
-        //assert_eq!(crossword.width, 20);
-        //assert_eq!(crossword.height, 7);
-        //assert_eq!(
-        //    crossword.words,
-        //    vec![
-        //        Word::new("Crossword".into()),
-        //        Word::new("Korsord".into()),
-        //        Word::new("クロスワード".into()),
-        //        Word::new("填字遊戲".into())
-        //    ]
-        //);
-        //assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('0'));
-        //assert!(crossword.cells.iter().all(|cell| !cell.highlighted()));
+        let crossword = Crossword::try_from(indoc! {
+            "0K000000000000000000
+             0o000000000000000000
+             Crossword00000000000
+             0s0000クロスワード00000000
+             0o000000000000000000
+             0r00填字遊戲000000000000
+             0d000000000000000000
+             Korsord Crossword クロスワード Kreuzworträtsel 填字遊戲"
+        })
+        .unwrap();
+
+        assert!(!crossword.solved);
+        assert_eq!(crossword.width, 20);
+        assert_eq!(crossword.height, 7);
+        assert_eq!(
+            crossword.words,
+            vec![
+                Word::new("Korsord".into()),
+                Word::new("Crossword".into()),
+                Word::new("クロスワード".into()),
+                Word::new("Kreuzworträtsel".into()),
+                Word::new("填字遊戲".into())
+            ]
+        );
+        assert_eq!(crossword[Point { x: 0, y: 0 }], Cell::new('0'));
+        assert!(crossword.cells.iter().all(|cell| !cell.highlighted()));
     }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_json() {
+        let crossword = Crossword::from_json(
+            indoc! {r#"
+            {
+              "headers": { "title": "Colors" },
+              "grid": ["r e d", "xyz"],
+              "words": ["red"]
+            }"#
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(crossword.headers, vec![("title".into(), "Colors".into())]);
+        assert_eq!(crossword.width, 3);
+        assert_eq!(crossword.height, 2);
+        assert_eq!(crossword.words, vec![Word::new("red".into())]);
+
+        assert_eq!(
+            Crossword::from_json(r#"{"grid": [], "words": []}"#, None),
+            Err(ParseError::EmptyGrid)
+        );
+        assert!(matches!(
+            Crossword::from_json(r#"{"grid": ["ab", "c"], "words": []}"#, None),
+            Err(ParseError::Json { line: None, .. })
+        ));
+        assert_eq!(
+            Crossword::from_json("{\n  \"grid\": 1\n}", None),
+            Err(ParseError::Json {
+                message: "invalid type: integer `1`, expected a sequence".into(),
+                line: Some(2),
+                column: Some(11)
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_ipuz() {
+        let crossword = Crossword::from_ipuz(
+            indoc! {r#"
+            ipuz({
+              "version": "http://ipuz.org/v2",
+              "kind": ["http://ipuz.org/wordsearch#1"],
+              "title": "Colors",
+              "dimensions": { "width": 3, "height": 2 },
+              "puzzle": [["R", "E", "D"], [{ "cell": "X" }, "Y", 7]],
+              "solution": { "RED": [[0, 0], [2, 0]], "BLUE": null }
+            })"#
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(crossword.headers, vec![("title".into(), "Colors".into())]);
+        assert_eq!(crossword[Point { x: 0, y: 1 }], Cell::new('X'));
+        assert_eq!(crossword[Point { x: 2, y: 1 }], Cell::new('7'));
+        assert_eq!(
+            crossword.words,
+            vec![Word::new("RED".into()), Word::new("BLUE".into())]
+        );
+
+        assert!(matches!(
+            Crossword::from_ipuz(
+                indoc! {r#"
+                {
+                  "kind": ["http://ipuz.org/crossword#1"],
+                  "puzzle": [["A"]]
+                }"#
+                },
+                None
+            ),
+            Err(ParseError::Json { line: None, .. })
+        ));
     }
+}
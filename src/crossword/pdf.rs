@@ -0,0 +1,483 @@
+use super::Crossword;
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use pdf_writer::{
+    types::{CidFontType, FontFlags, LineCapStyle, SystemInfo, UnicodeCmap},
+    Content, Filter, Finish, Name, Pdf, Rect, Ref, Str,
+};
+use std::collections::BTreeMap;
+use ttf_parser::{Face, GlyphId, Tag};
+
+/// The size of an A4 page in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+const TITLE_SIZE: f32 = 20.0;
+const WORD_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 15.0;
+const MAX_CELL_SIZE: f32 = 28.0;
+
+/// Light colors for the lines over found words on answer key pages, which are drawn below the letters.
+const COLORS: &[(f32, f32, f32)] = &[
+    (1.0, 0.75, 0.75),
+    (0.75, 0.9, 1.0),
+    (0.8, 1.0, 0.75),
+    (1.0, 0.9, 0.6),
+    (0.9, 0.8, 1.0),
+    (0.7, 1.0, 0.95),
+    (1.0, 0.8, 0.95),
+    (0.9, 0.9, 0.9),
+];
+
+const FONT_NAME: Name = Name(b"F1");
+
+/// An error while writing a PDF.
+#[derive(Debug, PartialEq)]
+pub enum PdfError {
+    /// The font file could not be read.
+    InvalidFont,
+    /// The font has no glyph for a character in one of the crosswords.
+    MissingGlyph(char),
+    /// The font has CFF2 outlines, which PDF can't embed.
+    UnsupportedFont,
+}
+
+/// The font that is embedded into the PDF and the glyphs that were used.
+struct Font<'a> {
+    face: Face<'a>,
+    /// The used glyphs and their characters by the codes they are shown with.
+    used: BTreeMap<u16, (GlyphId, char)>,
+}
+
+impl<'a> Font<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, PdfError> {
+        let face = Face::parse(data, 0).map_err(|_| PdfError::InvalidFont)?;
+        if face.raw_face().table(Tag::from_bytes(b"CFF2")).is_some() {
+            return Err(PdfError::UnsupportedFont);
+        }
+        let used = BTreeMap::new();
+
+        Ok(Self { face, used })
+    }
+
+    /// Whether the glyphs have CFF outlines instead of TrueType outlines.
+    fn is_cff(&self) -> bool {
+        self.face
+            .raw_face()
+            .table(Tag::from_bytes(b"CFF "))
+            .is_some()
+    }
+
+    /// Returns the code that shows the glyph, which is its CID in CID-keyed CFF fonts and the glyph ID otherwise.
+    fn code(&self, glyph: GlyphId) -> u16 {
+        self.face
+            .tables()
+            .cff
+            .and_then(|cff| cff.glyph_cid(glyph))
+            .unwrap_or(glyph.0)
+    }
+
+    /// Converts units of the font to points at this font size.
+    fn scale(&self, units: f32, size: f32) -> f32 {
+        units * size / f32::from(self.face.units_per_em())
+    }
+
+    fn glyph(&self, char: char) -> Result<GlyphId, PdfError> {
+        self.face
+            .glyph_index(char)
+            .ok_or(PdfError::MissingGlyph(char))
+    }
+
+    fn width(&self, text: &str, size: f32) -> Result<f32, PdfError> {
+        let mut width = 0.0;
+        for char in text.chars() {
+            let advance = self.face.glyph_hor_advance(self.glyph(char)?).unwrap_or(0);
+            width += self.scale(f32::from(advance), size);
+        }
+        Ok(width)
+    }
+
+    /// Encodes the text as the codes of its glyphs, which are what the text is shown with.
+    fn encode(&mut self, text: &str) -> Result<Vec<u8>, PdfError> {
+        let mut encoded = Vec::with_capacity(2 * text.len());
+        for char in text.chars() {
+            let glyph = self.glyph(char)?;
+            let code = self.code(glyph);
+            self.used.insert(code, (glyph, char));
+            encoded.extend(code.to_be_bytes());
+        }
+        Ok(encoded)
+    }
+}
+
+/// Draws pages with text in the embedded font.
+struct Page<'a, 'b> {
+    content: Content,
+    font: &'b mut Font<'a>,
+}
+
+impl Page<'_, '_> {
+    /// Shows text with its left edge at `x` and its baseline at `y`.
+    fn text(&mut self, x: f32, y: f32, size: f32, text: &str) -> Result<(), PdfError> {
+        let encoded = self.font.encode(text)?;
+        self.content
+            .begin_text()
+            .set_font(FONT_NAME, size)
+            .next_line(x, y)
+            .show(Str(&encoded))
+            .end_text();
+        Ok(())
+    }
+
+    /// Shows text centered around `x` and `y`.
+    fn centered_text(&mut self, x: f32, y: f32, size: f32, text: &str) -> Result<(), PdfError> {
+        let width = self.font.width(text, size)?;
+        self.text(x - width / 2.0, y - size * 0.35, size, text)
+    }
+
+    /// Draws a page with the title, the grid and the word bank.
+    ///
+    /// On answer key pages, every found word has a line over it.
+    fn crossword(
+        &mut self,
+        title: &str,
+        crossword: &Crossword,
+        answers: bool,
+    ) -> Result<(), PdfError> {
+        let content_width = PAGE_WIDTH - 2.0 * MARGIN;
+
+        let mut y = PAGE_HEIGHT - MARGIN - TITLE_SIZE;
+        self.text(MARGIN, y, TITLE_SIZE, title)?;
+        y -= TITLE_SIZE;
+
+        // Lay out the word bank in as many columns as fit on the page
+        let mut column_width: f32 = 0.0;
+        for word in &crossword.words {
+            column_width = column_width.max(self.font.width(&word.str, WORD_SIZE)?);
+        }
+        column_width += WORD_SIZE;
+        let columns = ((content_width / column_width) as usize).max(1);
+        let rows = crossword.words.len().div_ceil(columns);
+        let bank_height = rows as f32 * LINE_HEIGHT;
+
+        // The grid gets all the room that is left
+        let grid_room = y - MARGIN - bank_height - LINE_HEIGHT;
+        let cell_size = (content_width / crossword.width as f32)
+            .min(grid_room / crossword.height as f32)
+            .min(MAX_CELL_SIZE);
+        let grid_width = cell_size * crossword.width as f32;
+        let grid_height = cell_size * crossword.height as f32;
+        let left = (PAGE_WIDTH - grid_width) / 2.0;
+        let top = y;
+        let center = |x: usize, y: usize| {
+            (
+                left + (x as f32 + 0.5) * cell_size,
+                top - (y as f32 + 0.5) * cell_size,
+            )
+        };
+
+        if answers {
+            self.content
+                .set_line_width(cell_size * 0.7)
+                .set_line_cap(LineCapStyle::RoundCap);
+            let placements = crossword.words.iter().flat_map(|word| &word.placements);
+            for (placement, &(r, g, b)) in placements.zip(COLORS.iter().cycle()) {
+                let (start_x, start_y) = center(placement.start.x, placement.start.y);
+                let end = placement.end();
+                let (end_x, end_y) = center(end.x, end.y);
+                self.content
+                    .set_stroke_rgb(r, g, b)
+                    .move_to(start_x, start_y)
+                    .line_to(end_x, end_y)
+                    .stroke();
+            }
+        }
+
+        self.content
+            .set_line_width(1.0)
+            .set_stroke_rgb(0.0, 0.0, 0.0)
+            .rect(left, top - grid_height, grid_width, grid_height)
+            .stroke();
+        for (y, row) in crossword.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let (x, y) = center(x, y);
+                self.centered_text(x, y, cell_size * 0.6, cell.grapheme.as_str())?;
+            }
+        }
+
+        let bank_top = top - grid_height - LINE_HEIGHT;
+        for (i, word) in crossword.words.iter().enumerate() {
+            self.text(
+                MARGIN + (i % columns) as f32 * column_width,
+                bank_top - (i / columns + 1) as f32 * LINE_HEIGHT,
+                WORD_SIZE,
+                &word.str,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a book with a page for every crossword, followed by the answer keys if `answer_key` is set.
+///
+/// The crosswords come with the titles to print above them. The font, which is a TrueType or OpenType file,
+/// is embedded so that the PDF looks the same everywhere, whichever characters the crosswords use.
+pub fn write_book(
+    crosswords: &[(String, Crossword)],
+    font_data: &[u8],
+    answer_key: bool,
+) -> Result<Vec<u8>, PdfError> {
+    let mut font = Font::new(font_data)?;
+
+    let mut pages = Vec::new();
+    for (title, crossword) in crosswords {
+        let mut page = Page {
+            content: Content::new(),
+            font: &mut font,
+        };
+        page.crossword(title, crossword, false)?;
+        pages.push(page.content.finish());
+    }
+    if answer_key {
+        for (title, crossword) in crosswords {
+            let mut page = Page {
+                content: Content::new(),
+                font: &mut font,
+            };
+            page.crossword(&format!("{} – Answers", title), crossword, true)?;
+            pages.push(page.content.finish());
+        }
+    }
+
+    let mut pdf = Pdf::new();
+    let mut next_id = Ref::new(1);
+    let mut alloc = || next_id.bump();
+
+    let catalog_id = alloc();
+    let page_tree_id = alloc();
+    let font_id = alloc();
+    let cid_font_id = alloc();
+    let descriptor_id = alloc();
+    let font_file_id = alloc();
+    let to_unicode_id = alloc();
+    let page_ids: Vec<_> = pages.iter().map(|_| (alloc(), alloc())).collect();
+
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().map(|&(page_id, _)| page_id))
+        .count(page_ids.len() as i32);
+    for (content, &(page_id, content_id)) in pages.iter().zip(&page_ids) {
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().fonts().pair(FONT_NAME, font_id);
+        page.finish();
+        pdf.stream(content_id, content);
+    }
+
+    write_font(
+        &mut pdf,
+        &font,
+        font_data,
+        [
+            font_id,
+            cid_font_id,
+            descriptor_id,
+            font_file_id,
+            to_unicode_id,
+        ],
+    );
+
+    Ok(pdf.finish())
+}
+
+/// Embeds the whole font file, with glyph IDs or the CIDs of CID-keyed CFF fonts as the character codes.
+///
+/// Fonts with TrueType outlines are embedded as TrueType fonts and fonts with CFF outlines as OpenType fonts.
+fn write_font(pdf: &mut Pdf, font: &Font, font_data: &[u8], ids: [Ref; 5]) {
+    let [font_id, cid_font_id, descriptor_id, font_file_id, to_unicode_id] = ids;
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"Identity"),
+        supplement: 0,
+    };
+    // PDF names can't contain spaces, which PostScript names don't have anyway
+    let base_font = font
+        .face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+        .filter_map(|name| name.to_string())
+        .find(|name| name.chars().all(|char| char.is_ascii_graphic()))
+        .unwrap_or_else(|| "Crustword".to_string());
+    let units = |units: i16| font.scale(f32::from(units), 1000.0);
+
+    pdf.type0_font(font_id)
+        .base_font(Name(base_font.as_bytes()))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid_font_id)
+        .to_unicode(to_unicode_id);
+
+    let mut cid_font = pdf.cid_font(cid_font_id);
+    cid_font
+        .subtype(if font.is_cff() {
+            CidFontType::Type0
+        } else {
+            CidFontType::Type2
+        })
+        .base_font(Name(base_font.as_bytes()))
+        .system_info(system_info)
+        .font_descriptor(descriptor_id);
+    // CFF fonts map CIDs to glyphs themselves
+    if !font.is_cff() {
+        cid_font.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    let mut widths = cid_font.widths();
+    for (&code, &(glyph, _)) in &font.used {
+        let advance = font.face.glyph_hor_advance(glyph).unwrap_or(0);
+        widths.consecutive(code, [font.scale(f32::from(advance), 1000.0)]);
+    }
+    widths.finish();
+    cid_font.finish();
+
+    let bbox = font.face.global_bounding_box();
+    let mut descriptor = pdf.font_descriptor(descriptor_id);
+    descriptor
+        .name(Name(base_font.as_bytes()))
+        .flags(FontFlags::SYMBOLIC)
+        .bbox(Rect::new(
+            units(bbox.x_min),
+            units(bbox.y_min),
+            units(bbox.x_max),
+            units(bbox.y_max),
+        ))
+        .italic_angle(0.0)
+        .ascent(units(font.face.ascender()))
+        .descent(units(font.face.descender()))
+        .cap_height(units(
+            font.face.capital_height().unwrap_or(font.face.ascender()),
+        ))
+        .stem_v(80.0);
+    if font.is_cff() {
+        descriptor.font_file3(font_file_id);
+    } else {
+        descriptor.font_file2(font_file_id);
+    }
+    descriptor.finish();
+
+    let compressed = compress_to_vec_zlib(font_data, 6);
+    let mut font_file = pdf.stream(font_file_id, &compressed);
+    font_file.filter(Filter::FlateDecode);
+    if font.is_cff() {
+        font_file.pair(Name(b"Subtype"), Name(b"OpenType"));
+    }
+    font_file.finish();
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+    for (&code, &(_, char)) in &font.used {
+        cmap.pair(code, char);
+    }
+    pdf.cmap(to_unicode_id, &cmap.finish());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a font that maps printable ASCII characters to glyphs, with an outline table that is only checked for.
+    fn font(outline_tag: &[u8; 4]) -> Vec<u8> {
+        let glyphs = 96u16;
+        let mut head = vec![0; 54];
+        head[..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+        hhea[4..6].copy_from_slice(&800u16.to_be_bytes());
+        hhea[34..36].copy_from_slice(&glyphs.to_be_bytes());
+        let mut maxp = 0x0000_5000u32.to_be_bytes().to_vec();
+        maxp.extend(glyphs.to_be_bytes());
+        // A format 0 subtable for the Unicode platform
+        let mut cmap = [0u16, 1, 0, 3]
+            .iter()
+            .flat_map(|n| n.to_be_bytes())
+            .collect::<Vec<_>>();
+        cmap.extend(12u32.to_be_bytes());
+        cmap.extend([0u16, 262, 0].iter().flat_map(|n| n.to_be_bytes()));
+        cmap.extend((0..=255u8).map(|byte| if byte >= 0x20 { byte - 0x1F } else { 0 }));
+        let hmtx = (0..glyphs)
+            .flat_map(|_| [600u16, 0])
+            .flat_map(u16::to_be_bytes)
+            .collect();
+
+        let mut tables: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (outline_tag, vec![0; 4]),
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"maxp", maxp),
+        ];
+        tables.sort_by_key(|&(tag, _)| tag);
+
+        let version: &[u8; 4] = if outline_tag == b"glyf" {
+            b"\0\x01\0\0"
+        } else {
+            b"OTTO"
+        };
+        let mut data = version.to_vec();
+        data.extend(
+            [tables.len() as u16, 0, 0, 0]
+                .iter()
+                .flat_map(|n| n.to_be_bytes()),
+        );
+        let mut offset = data.len() + 16 * tables.len();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend(0u32.to_be_bytes());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+        for (_, table) in tables {
+            data.extend(table);
+        }
+        data
+    }
+
+    #[test]
+    fn test_write_book() {
+        let crossword = Crossword::try_from("abc\nabc\n\nabc").unwrap();
+        let crosswords = [("Test".to_string(), crossword)];
+
+        let pdf = write_book(&crosswords, &font(b"glyf"), false).unwrap();
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/CIDFontType2"));
+        assert!(pdf.contains("/FontFile2"));
+
+        // CFF fonts such as most .otf files have to be embedded as OpenType
+        let pdf = write_book(&crosswords, &font(b"CFF "), false).unwrap();
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/CIDFontType0"));
+        assert!(pdf.contains("/FontFile3"));
+        assert!(pdf.contains("/Subtype /OpenType"));
+        assert!(!pdf.contains("/CIDToGIDMap"));
+
+        assert_eq!(
+            write_book(&crosswords, &font(b"CFF2"), false),
+            Err(PdfError::UnsupportedFont)
+        );
+    }
+
+    #[test]
+    fn test_write_book_invalid_font() {
+        let crossword = Crossword::try_from("abc\nabc").unwrap();
+
+        assert_eq!(
+            write_book(&[("Test".to_string(), crossword)], b"not a font", false),
+            Err(PdfError::InvalidFont)
+        );
+    }
+}
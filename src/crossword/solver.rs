@@ -1,162 +1,89 @@
-use super::Crossword;
+use super::{Crossword, Direction, Placement, Solution, SolvedWord};
 use crate::util::{escape_sequences, Point};
 use std::{io, thread, time};
 
-macro_rules! cardinal_direction_fn {
-    ($direction:ident, $rev:literal, $size:ident, $coord:ident) => {
-        #[doc = concat!("Looks for words ", stringify!($direction), " from this point.")]
-        fn $direction(&mut self, point: Point) {
-            let result = self
-                .words
-                .iter()
-                .enumerate()
-                .filter(|(_, word)| !word.marked)
-                .find(|(_, word)| {
-                    if $rev {
-                        (point.$coord..point.$coord + word.len)
-                            .rev()
-                            .map(|$coord| self[Point { $coord, ..point }].char)
-                            .eq(word.chars())
-                    } else {
-                        (point.$coord..point.$coord + word.len)
-                            .map(|$coord| self[Point { $coord, ..point }].char)
-                            .eq(word.chars())
-                    }
-                });
-
-            if let Some((i, word)) = result {
-                for $coord in point.$coord..point.$coord + word.len {
-                    self.highlight(Point { $coord, ..point });
-                }
-                self.words[i].marked = true;
-            }
-        }
-    };
-}
-
 impl Crossword<'_> {
-    cardinal_direction_fn!(north, true, height, y);
-    cardinal_direction_fn!(east, false, width, x);
-    cardinal_direction_fn!(south, false, height, y);
-    cardinal_direction_fn!(west, true, width, x);
-
-    // TODO: refactor the following with a macro called `ordinal_direction_fn`
-    fn northeast(&mut self, point: Point) {
-        let result = self
-            .words
-            .iter()
-            .enumerate()
-            .filter(|(_, word)| !word.marked)
-            .find(|(_, word)| {
-                (point.y..point.y + word.len)
-                    .rev()
-                    .enumerate()
-                    .map(|(x, y)| self[Point { x: point.x + x, y }].char)
-                    .eq(word.chars())
-            });
-
-        if let Some((i, word)) = result {
-            for (x, y) in (point.y..point.y + word.len).rev().enumerate() {
-                self.highlight(Point { x: point.x + x, y });
-            }
-            self.words[i].marked = true;
-        }
-    }
-    fn southeast(&mut self, point: Point) {
-        let result = self
+    /// Looks for words starting at this point and going in the given direction.
+    ///
+    /// If `all_occurrences` is `false`, only the first word not found yet is looked for.
+    fn find(&mut self, start: Point, direction: Direction, all_occurrences: bool) {
+        let matches: Vec<(usize, Vec<Point>)> = self
             .words
             .iter()
             .enumerate()
-            .filter(|(_, word)| !word.marked)
-            .find(|(_, word)| {
-                (point.x..point.x + word.len)
-                    .enumerate()
-                    .map(|(y, x)| self[Point { x, y: point.y + y }].char)
-                    .eq(word.chars())
-            });
-
-        if let Some((i, word)) = result {
-            for (y, x) in (point.x..point.x + word.len).enumerate() {
-                self.highlight(Point { x, y: point.y + y });
-            }
-            self.words[i].marked = true;
-        }
-    }
-    fn southwest(&mut self, point: Point) {
-        let result = self
-            .words
-            .iter()
-            .enumerate()
-            .filter(|(_, word)| !word.marked)
-            .find(|(_, word)| {
-                (point.y..point.y + word.len)
-                    .enumerate()
-                    .map(|(x, y)| {
-                        self[Point {
-                            x: point.x.saturating_sub(x),
-                            y,
-                        }]
-                        .char
-                    })
-                    .eq(word.chars())
-            });
-
-        if let Some((i, word)) = result {
-            for (x, y) in (point.y..point.y + word.len).enumerate() {
-                self.highlight(Point { x: point.x - x, y });
+            .filter(|(_, word)| all_occurrences || !word.marked)
+            .filter_map(|(i, word)| {
+                let points = direction.points(start, word.len)?;
+                let matches = points
+                    .iter()
+                    .zip(word.graphemes())
+                    .all(|(&point, grapheme)| {
+                        self.get(point).is_some_and(|cell| {
+                            self.matching
+                                .graphemes_match(cell.grapheme.as_str(), grapheme)
+                        })
+                    });
+                matches.then_some((i, points))
+            })
+            .take(if all_occurrences { usize::MAX } else { 1 })
+            .collect();
+
+        for (i, points) in matches {
+            // Don't count the same cells twice
+            if self.words[i]
+                .placements
+                .iter()
+                .any(|placement| placement.covers(&points))
+            {
+                continue;
             }
-            self.words[i].marked = true;
+
+            self.found(i, direction, points);
         }
     }
-    fn northwest(&mut self, point: Point) {
-        let result = self
-            .words
-            .iter()
-            .enumerate()
-            .filter(|(_, word)| !word.marked)
-            .find(|(_, word)| {
-                (point.x..point.x + word.len)
-                    .rev()
-                    .enumerate()
-                    .map(|(y, x)| {
-                        self[Point {
-                            x,
-                            y: point.y.saturating_sub(y),
-                        }]
-                        .char
-                    })
-                    .eq(word.chars())
-            });
-
-        if let Some((i, word)) = result {
-            for (y, x) in (point.x..point.x + word.len).rev().enumerate() {
-                self.highlight(Point { x, y: point.y - y });
-            }
-            self.words[i].marked = true;
+
+    /// Highlights the cells of the word at index `i` and records where it was found.
+    fn found(&mut self, i: usize, direction: Direction, points: Vec<Point>) {
+        for &point in &points {
+            self.highlight(point);
         }
+        let word = &mut self.words[i];
+        word.marked = true;
+        word.placements.push(Placement {
+            start: points[0],
+            direction,
+            points,
+        });
     }
 
-    pub fn solve(&mut self, watch: bool) {
+    /// Solves the crossword by highlighting all words found and returns where each word was found.
+    ///
+    /// Only words written in one of the given directions are found.
+    /// If `all_occurrences` is `true`, every occurrence of every word is found
+    /// instead of stopping at the first occurrence.
+    pub fn solve(
+        &mut self,
+        watch: bool,
+        directions: &[Direction],
+        all_occurrences: bool,
+    ) -> Solution {
         let mut out = io::stdout();
 
         for y in 0..self.height {
             for x in 0..self.width {
                 let point = Point { x, y };
 
-                // Try to find words clockwise from this cell
-
-                self.north(point);
-                self.northeast(point);
-                self.east(point);
-                self.southeast(point);
-                self.south(point);
-                self.southwest(point);
-                self.west(point);
-                self.northwest(point);
+                // Try to find words starting at this cell
+                for &direction in directions {
+                    self.find(point, direction, all_occurrences);
+                }
 
                 if watch {
-                    print!("{}", self);
-                    escape_sequences::cursor_up(&mut out, self.height + 4).unwrap();
+                    // Headers and warnings about words found more than once take up lines too,
+                    // so count the lines actually written to draw the next frame over this one
+                    let frame = self.to_string();
+                    print!("{}", frame);
+                    escape_sequences::cursor_up(&mut out, frame.matches('\n').count()).unwrap();
 
                     thread::sleep(time::Duration::from_secs_f32(0.1));
                 }
@@ -164,12 +91,24 @@ impl Crossword<'_> {
         }
 
         self.solved = true;
+
+        let words = self
+            .words
+            .iter()
+            .map(|word| SolvedWord {
+                word: word.str.to_string(),
+                placements: word.placements.clone(),
+            })
+            .collect();
+
+        Solution { words }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crossword::DARKEST_GRAY;
     use indoc::indoc;
 
     #[test]
@@ -187,7 +126,7 @@ mod tests {
         })
         .unwrap();
 
-        crossword.solve(false);
+        crossword.solve(false, &Direction::ALL, false);
 
         assert!(crossword.words.iter().all(|word| word.marked));
         assert!(crossword.solved);
@@ -208,7 +147,7 @@ mod tests {
         })
         .unwrap();
 
-        crossword.solve(false);
+        crossword.solve(false, &Direction::ALL, false);
 
         assert!(crossword.words.iter().all(|word| word.marked));
         assert!(crossword.solved);
@@ -229,9 +168,206 @@ mod tests {
         })
         .unwrap();
 
-        crossword.solve(false);
+        crossword.solve(false, &Direction::ALL, false);
 
         assert!(crossword.words.iter().all(|word| word.marked));
         assert!(crossword.solved);
     }
+
+    #[test]
+    fn test_solution() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "haaaa
+             drowa
+             aaaaa
+             aaaaa
+             word tree house"
+        })
+        .unwrap();
+
+        let solution = crossword.solve(false, &Direction::ALL, false);
+
+        assert_eq!(
+            solution.words,
+            vec![
+                SolvedWord {
+                    word: "word".to_string(),
+                    placements: vec![Placement {
+                        start: Point { x: 3, y: 1 },
+                        direction: Direction::West,
+                        points: vec![
+                            Point { x: 3, y: 1 },
+                            Point { x: 2, y: 1 },
+                            Point { x: 1, y: 1 },
+                            Point { x: 0, y: 1 },
+                        ],
+                    }],
+                },
+                SolvedWord {
+                    word: "tree".to_string(),
+                    placements: vec![],
+                },
+                SolvedWord {
+                    word: "house".to_string(),
+                    placements: vec![],
+                },
+            ]
+        );
+        assert_eq!(solution.words[0].placements[0].end(), Point { x: 0, y: 1 });
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "a b b a
+             x å y z
+             ABBA Å"
+        })
+        .unwrap();
+
+        let solution = crossword.solve(false, &Direction::ALL, false);
+        assert!(solution.words.iter().all(|word| word.placements.is_empty()));
+
+        let mut crossword = Crossword::try_from(indoc! {
+            "case: insensitive
+
+             a b b a
+             x å y z
+             ABBA Å"
+        })
+        .unwrap();
+
+        let solution = crossword.solve(false, &Direction::ALL, false);
+        assert!(solution
+            .words
+            .iter()
+            .all(|word| !word.placements.is_empty()));
+    }
+
+    #[test]
+    fn test_fold_diacritics() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "fold: diacritics
+
+             k v ä l l
+             ü b e r x
+             kvall uber"
+        })
+        .unwrap();
+
+        let solution = crossword.solve(false, &Direction::ALL, false);
+        assert!(solution
+            .words
+            .iter()
+            .all(|word| !word.placements.is_empty()));
+        // The grid keeps its diacritics
+        assert_eq!(crossword[Point { x: 2, y: 0 }].grapheme.as_str(), "ä");
+        assert!(crossword[Point { x: 2, y: 0 }].highlighted());
+    }
+
+    #[test]
+    fn test_leftover() {
+        let mut crossword = Crossword::try_from(indoc! {
+            "h c a t
+             d o g i
+             b i r d
+             cat dog bird"
+        })
+        .unwrap();
+
+        assert_eq!(crossword.leftover(), "hcatdogibird");
+        crossword.solve(false, &Direction::ALL, false);
+        assert_eq!(crossword.leftover(), "hi");
+    }
+
+    #[test]
+    fn test_highlight_many_words() {
+        // Every cell is part of hundreds of occurrences
+        let row = "a".repeat(15);
+        let words = (1..=15).map(|len| "a".repeat(len)).collect::<Vec<_>>();
+        let crossword_str = format!("{}\n{}", vec![row; 15].join("\n"), words.join(" "));
+        let mut crossword = Crossword::try_from(crossword_str.as_ref()).unwrap();
+
+        crossword.solve(false, &Direction::ALL, true);
+
+        assert!(crossword
+            .cells
+            .iter()
+            .all(|cell| cell.highlighted() && cell.highlighting >= DARKEST_GRAY));
+        assert_eq!(crossword[Point { x: 7, y: 7 }].highlighting, DARKEST_GRAY);
+    }
+
+    #[test]
+    fn test_edges() {
+        // If words could run off the grid, border cells would be repeated
+        // and each of these words would be found at one of the edges
+        for direction in Direction::ALL {
+            let mut crossword = Crossword::try_from(indoc! {
+                "abc
+                 def
+                 ghi
+                 aa bb cc dd ff gg hh ii"
+            })
+            .unwrap();
+
+            let solution = crossword.solve(false, &[direction], true);
+
+            assert!(
+                solution.words.iter().all(|word| word.placements.is_empty()),
+                "found a word running off the grid going {}",
+                direction
+            );
+            assert!(crossword.cells.iter().all(|cell| !cell.highlighted()));
+        }
+    }
+
+    #[test]
+    fn test_edges_in_bounds() {
+        for direction in Direction::ALL {
+            let mut crossword = Crossword::try_from(indoc! {
+                "aaa
+                 aaa
+                 aaa
+                 aaaa aaa"
+            })
+            .unwrap();
+
+            let solution = crossword.solve(false, &[direction], true);
+
+            assert!(solution.words[0].placements.is_empty());
+            assert!(!solution.words[1].placements.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_all_occurrences() {
+        let source = indoc! {
+            "treeaaa
+             aaaaaaa
+             aaaeert
+             aalevel
+             tree level"
+        };
+
+        let mut crossword = Crossword::try_from(source).unwrap();
+        let solution = crossword.solve(false, &Direction::ALL, false);
+        assert_eq!(solution.words[0].placements.len(), 1);
+        assert_eq!(solution.words[1].placements.len(), 1);
+
+        let mut crossword = Crossword::try_from(source).unwrap();
+        let solution = crossword.solve(false, &Direction::ALL, true);
+        assert_eq!(
+            solution.words[0]
+                .placements
+                .iter()
+                .map(|placement| (placement.start, placement.direction))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point { x: 0, y: 0 }, Direction::East),
+                (Point { x: 6, y: 2 }, Direction::West)
+            ]
+        );
+        // Palindromes read the same in both directions but only occur once
+        assert_eq!(solution.words[1].placements.len(), 1);
+    }
 }
@@ -0,0 +1,213 @@
+use super::Crossword;
+use crate::util::escape;
+use std::fmt::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// How a crossword is drawn as SVG.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// The font family, such as `monospace` or `Noto Sans Mono CJK`.
+    pub font: String,
+    /// The width and height of a cell in pixels.
+    pub cell_size: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            font: "monospace".to_string(),
+            cell_size: 32.0,
+        }
+    }
+}
+
+/// Rounds to hundredths of a pixel to keep the output short.
+fn round(pixels: f64) -> f64 {
+    (pixels * 100.0).round() / 100.0
+}
+
+/// Characters take up this much of a cell.
+const FONT_SCALE: f64 = 0.6;
+/// The approximate advance of a half-width character relative to the font size.
+const HALF_WIDTH: f64 = 0.6;
+
+impl Crossword<'_> {
+    /// Renders the grid and the word list as SVG.
+    ///
+    /// If `answers` is set and the crossword has been solved,
+    /// every found word gets a line from its first to its last cell in a color of its own.
+    pub fn to_svg(&self, options: &SvgOptions, answers: bool) -> String {
+        let mut svg = String::new();
+        self.write_svg(&mut svg, options, answers).unwrap();
+        svg
+    }
+
+    fn write_svg(&self, svg: &mut String, options: &SvgOptions, answers: bool) -> fmt::Result {
+        let cell_size = options.cell_size;
+        let font_size = cell_size * FONT_SCALE;
+        let margin = cell_size / 2.0;
+        let grid_width = self.width as f64 * cell_size;
+        let grid_height = self.height as f64 * cell_size;
+
+        // Lay out the word list in as many columns as fit below the grid,
+        // measuring words in terminal columns so that full-width characters count twice
+        let line_height = font_size * 1.5;
+        let column_width = round(
+            self.words
+                .iter()
+                .map(|word| word.str.width())
+                .max()
+                .unwrap_or(0) as f64
+                * font_size
+                * HALF_WIDTH
+                + cell_size,
+        );
+        let columns = ((grid_width / column_width).floor() as usize).max(1);
+        let rows = self.words.len().div_ceil(columns);
+
+        let width = round(grid_width.max(columns as f64 * column_width) + 2.0 * margin);
+        let height = round(grid_height + rows as f64 * line_height + 3.0 * margin);
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="{2}">"#,
+            width,
+            height,
+            escape(&options.font)
+        )?;
+        if let Some(title) = self.title() {
+            writeln!(svg, "<title>{}</title>", escape(title))?;
+        }
+        writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" fill="white"/>"#,
+            width, height
+        )?;
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            margin, margin, grid_width, grid_height
+        )?;
+
+        writeln!(
+            svg,
+            r#"<g font-size="{}" text-anchor="middle" dominant-baseline="central">"#,
+            font_size
+        )?;
+        for (y, row) in self.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}">{}</text>"#,
+                    margin + (x as f64 + 0.5) * cell_size,
+                    margin + (y as f64 + 0.5) * cell_size,
+                    escape(cell.grapheme.as_str())
+                )?;
+            }
+        }
+        writeln!(svg, "</g>")?;
+
+        if answers && self.solved {
+            writeln!(
+                svg,
+                r#"<g stroke-width="{}" stroke-linecap="round" stroke-opacity="0.4">"#,
+                cell_size * 0.7
+            )?;
+            let placements = self.words.iter().flat_map(|word| &word.placements);
+            for (i, placement) in placements.enumerate() {
+                let end = placement.end();
+                // Spread the hues evenly using the golden angle so that neighboring words differ
+                let hue = (i as f64 * 137.5) % 360.0;
+                writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="hsl({}, 80%, 45%)"/>"#,
+                    margin + (placement.start.x as f64 + 0.5) * cell_size,
+                    margin + (placement.start.y as f64 + 0.5) * cell_size,
+                    margin + (end.x as f64 + 0.5) * cell_size,
+                    margin + (end.y as f64 + 0.5) * cell_size,
+                    hue
+                )?;
+            }
+            writeln!(svg, "</g>")?;
+        }
+
+        writeln!(
+            svg,
+            r#"<g font-size="{}" dominant-baseline="central">"#,
+            font_size
+        )?;
+        for (i, word) in self.words.iter().enumerate() {
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                round(margin + (i % columns) as f64 * column_width),
+                round(grid_height + 2.0 * margin + ((i / columns) as f64 + 0.5) * line_height),
+                escape(&word.str)
+            )?;
+        }
+        writeln!(svg, "</g>")?;
+
+        writeln!(svg, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossword::Direction;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_svg() {
+        // A phrase of full-width characters running diagonally, next to half-width filler
+        let mut crossword = Crossword::try_from(indoc! {
+            r#"title: Sweets & treats
+
+               ア x x
+               x イ x
+               x x ス
+               "ア イ ス" ケーキ"#
+        })
+        .unwrap();
+        let options = SvgOptions {
+            font: "Noto Sans Mono CJK JP".to_string(),
+            cell_size: 10.0,
+        };
+
+        let svg = crossword.to_svg(&options, true);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("font-family=\"Noto Sans Mono CJK JP\""));
+        assert!(svg.contains("<title>Sweets &amp; treats</title>"));
+        assert!(svg.contains("<text x=\"10\" y=\"10\">ア</text>"));
+        assert!(svg.contains("<text x=\"30\" y=\"30\">ス</text>"));
+        assert!(svg.contains(">ア イ ス</text>"));
+        assert!(!svg.contains("<line"));
+
+        crossword.solve(false, &Direction::ALL, false);
+        let svg = crossword.to_svg(&options, true);
+        // Only the phrase is found, from the top left to the bottom right cell
+        assert_eq!(svg.matches("<line").count(), 1);
+        assert!(svg.contains(
+            "<line x1=\"10\" y1=\"10\" x2=\"30\" y2=\"30\" stroke=\"hsl(0, 80%, 45%)\"/>"
+        ));
+        assert!(!crossword.to_svg(&options, false).contains("<line"));
+    }
+
+    #[test]
+    fn test_to_svg_full_width() {
+        let crossword = Crossword::try_from(indoc! {
+            "クロ
+             スワ
+             クロスワード"
+        })
+        .unwrap();
+        let options = SvgOptions {
+            font: "monospace".to_string(),
+            cell_size: 10.0,
+        };
+
+        // The word is 12 columns wide, so at 3.6 pixels per column it is wider than the grid
+        let svg = crossword.to_svg(&options, false);
+        assert!(svg.contains("width=\"63.2\""));
+    }
+}
@@ -4,9 +4,16 @@ mod args;
 mod crossword;
 mod util;
 
-use args::Command;
-use crossword::Crossword;
-use std::{io, process};
+use args::{ColorMode, Command, Format};
+use crossword::{Crossword, Matching, Normalization, ParseError, SvgOptions};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, IsTerminal, Write},
+    process,
+};
+use unicode_width::UnicodeWidthStr;
+use util::escape_sequences;
 
 type Error = &'static str;
 
@@ -18,35 +25,315 @@ fn main() {
             eprintln!("{}", err);
             process::exit(1);
         }
-        Ok(Command::Generate { watch, arg_words }) => {
-            let stdout = io::stdout();
-            let mut lock = stdout.lock();
-            match crossword::gen(watch, arg_words, &mut lock) {
+        Ok(Command::Generate {
+            watch,
+            options,
+            arg_words,
+            output,
+            format,
+            answer_key,
+            svg_options,
+            color,
+        }) => {
+            // Watch frames always go to stdout, which may be a terminal even if the output is a file
+            escape_sequences::set_enabled(use_color(color, &None));
+            // The output is only written once generating succeeded so that no file is left half-written
+            let mut buffer = Vec::new();
+            let result = if format == Format::Text {
+                crossword::gen(watch, arg_words, &options, &mut buffer)
+            } else {
+                gen_rendered(
+                    format,
+                    answer_key,
+                    &svg_options,
+                    arg_words,
+                    &options,
+                    &mut buffer,
+                )
+            };
+            match result {
                 Err(err) => {
                     eprintln!("crossword generation error: {}", err);
                     process::exit(1);
                 }
-                Ok(()) => {}
+                Ok(generated) => {
+                    let mut writer = open_output(output);
+                    if writer.write_all(&buffer).is_err() || writer.flush().is_err() {
+                        eprintln!("error writing output");
+                        process::exit(1);
+                    }
+                    if generated.rerolls == 0 {
+                        eprintln!("Checked that every word occurs exactly once.");
+                    } else {
+                        eprintln!(
+                            "Checked that every word occurs exactly once after regenerating {} times.",
+                            generated.rerolls
+                        );
+                    }
+                    if options.seed.is_none() {
+                        eprintln!(
+                            "Generated with seed {0}. Use `--seed {0}` to generate this crossword again.",
+                            generated.seed
+                        );
+                    }
+                }
             }
         }
         Ok(Command::Solve {
             watch,
+            directions,
+            all_occurrences,
+            ignore_case,
+            normalization,
+            folding,
+            message,
+            file_name,
             crossword_str,
+            output,
+            format,
+            answer_key,
+            svg_options,
+            color,
         }) => {
-            let crossword = Crossword::try_from(crossword_str.as_ref());
+            let output_color = use_color(color, &output);
+            escape_sequences::set_enabled(output_color);
+            let crossword = parse_crossword(&crossword_str, normalization);
             match crossword {
                 Err(err) => {
-                    eprintln!("crossword parsing error: {}", err);
+                    let file_name = if file_name == "-" {
+                        "<stdin>"
+                    } else {
+                        &file_name
+                    };
+                    print_diagnostic(file_name, &crossword_str, &err);
                     process::exit(1);
                 }
                 Ok(mut crossword) => {
-                    println!("Before:\n{}", crossword);
+                    // The normalization was already applied while parsing
+                    let headers = crossword.matching().clone();
+                    crossword.set_matching(Matching {
+                        ignore_case: ignore_case.unwrap_or(headers.ignore_case),
+                        folding: folding.unwrap_or(headers.folding),
+                        ..headers
+                    });
+                    let directions = directions.unwrap_or_else(|| crossword.directions().to_vec());
+                    let mut writer = open_output(output);
 
-                    crossword.solve(watch);
+                    let result = if format == Format::Text {
+                        writeln!(writer, "Before:\n{}", crossword).and_then(|()| {
+                            // Watch frames always go to stdout, which may be a terminal even if the output is a file
+                            escape_sequences::set_enabled(use_color(color, &None));
+                            let solution = crossword.solve(watch, &directions, all_occurrences);
+                            escape_sequences::set_enabled(output_color);
+
+                            writeln!(writer, "After:\n{}", crossword)?;
+                            write!(writer, "{}", solution)?;
+                            if message {
+                                writeln!(writer, "Hidden message: {}", crossword.leftover())?;
+                            }
+                            Ok(())
+                        })
+                    } else {
+                        crossword.solve(watch, &directions, all_occurrences);
+                        writeln!(
+                            writer,
+                            "{}",
+                            render(&crossword, format, answer_key, &svg_options)
+                        )
+                    }
+                    .and_then(|()| writer.flush());
+                    if result.is_err() {
+                        eprintln!("error writing output");
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "pdf")]
+        Ok(Command::Book {
+            directions,
+            answer_key,
+            font,
+            inputs,
+            output,
+        }) => {
+            let mut crosswords = Vec::with_capacity(inputs.len());
+            for (file_name, crossword_str) in &inputs {
+                match parse_crossword(crossword_str, None) {
+                    Err(err) => {
+                        print_diagnostic(file_name, crossword_str, &err);
+                        process::exit(1);
+                    }
+                    Ok(mut crossword) => {
+                        if answer_key {
+                            let directions = directions
+                                .clone()
+                                .unwrap_or_else(|| crossword.directions().to_vec());
+                            crossword.solve(false, &directions, false);
+                        }
+                        // Crosswords without a title are named after their file
+                        let title = crossword.title().map_or_else(
+                            || {
+                                std::path::Path::new(file_name)
+                                    .file_stem()
+                                    .map_or(file_name.clone(), |stem| {
+                                        stem.to_string_lossy().into_owned()
+                                    })
+                            },
+                            str::to_string,
+                        );
+                        crosswords.push((title, crossword));
+                    }
+                }
+            }
 
-                    println!("After:\n{}", crossword);
+            match crossword::write_book(&crosswords, &font, answer_key) {
+                Err(err) => {
+                    eprintln!("PDF error: {}", err);
+                    process::exit(1);
+                }
+                Ok(pdf) => {
+                    let mut writer = open_output(output);
+                    if writer
+                        .write_all(&pdf)
+                        .and_then(|()| writer.flush())
+                        .is_err()
+                    {
+                        eprintln!("error writing output");
+                        process::exit(1);
+                    }
                 }
             }
         }
     }
 }
+
+/// Parses a crossword in its text form or, if the `json` feature is enabled, its JSON or ipuz form.
+fn parse_crossword(
+    crossword_str: &str,
+    normalization: Option<Option<Normalization>>,
+) -> Result<Crossword<'_>, ParseError> {
+    #[cfg(feature = "json")]
+    {
+        let trimmed = crossword_str.trim_start();
+        if trimmed.starts_with("ipuz(")
+            || trimmed.starts_with('{') && crossword_str.contains("http://ipuz.org/")
+        {
+            return Crossword::from_ipuz(crossword_str, normalization);
+        }
+        if trimmed.starts_with('{') {
+            return Crossword::from_json(crossword_str, normalization);
+        }
+    }
+
+    Crossword::parse(crossword_str, normalization)
+}
+
+/// Renders a crossword in any format but the text form.
+fn render(
+    crossword: &Crossword,
+    format: Format,
+    answer_key: bool,
+    svg_options: &SvgOptions,
+) -> String {
+    match format {
+        Format::Text => crossword.to_string(),
+        Format::Html => crossword.to_html(answer_key),
+        Format::Svg => crossword.to_svg(svg_options, answer_key),
+        #[cfg(feature = "json")]
+        Format::Json => crossword.to_json(),
+        #[cfg(feature = "json")]
+        Format::Ipuz => crossword.to_ipuz(),
+    }
+}
+
+/// Generates a crossword and writes it in any format but the text form.
+///
+/// If `answer_key` is set, the crossword is solved so the answers can be shown.
+fn gen_rendered(
+    format: Format,
+    answer_key: bool,
+    svg_options: &SvgOptions,
+    arg_words: args::ArgWords,
+    options: &crossword::GenOptions,
+    writer: &mut impl Write,
+) -> Result<crossword::Generated, crossword::GenError> {
+    let mut text = Vec::new();
+    let generated = crossword::gen(false, arg_words, options, &mut text)?;
+    // The generator writes the text form, which we read back in
+    let text = String::from_utf8(text).map_err(|_| crossword::GenError::WritingFailed)?;
+    let mut crossword =
+        Crossword::try_from(text.as_ref()).map_err(|_| crossword::GenError::WritingFailed)?;
+    if answer_key {
+        let directions = crossword.directions().to_vec();
+        crossword.solve(false, &directions, false);
+    }
+    writeln!(
+        writer,
+        "{}",
+        render(&crossword, format, answer_key, svg_options)
+    )
+    .map_err(|_| crossword::GenError::WritingFailed)?;
+    Ok(generated)
+}
+
+/// Whether to use colors and other escape sequences when writing to the output file or stdout.
+///
+/// See <https://no-color.org>.
+fn use_color(color: ColorMode, output: &Option<String>) -> bool {
+    match color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            output.is_none()
+                && io::stdout().is_terminal()
+                && env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+        }
+    }
+}
+
+/// Opens the file to write the output to or stdout if there is no file.
+fn open_output(output: Option<String>) -> Box<dyn Write> {
+    if let Some(output) = output {
+        match File::create(output) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(_) => {
+                eprintln!("error creating output file");
+                process::exit(1);
+            }
+        }
+    } else {
+        Box::new(io::stdout().lock())
+    }
+}
+
+/// Prints a parsing error along with the line it is on, similar to a compiler.
+fn print_diagnostic(file_name: &str, crossword_str: &str, err: &ParseError) {
+    eprintln!("crossword parsing error: {}", err);
+
+    let line_number = if let Some(line_number) = err.line() {
+        line_number
+    } else {
+        eprintln!(" --> {}", file_name);
+        return;
+    };
+    let line = crossword_str
+        .lines()
+        .nth(line_number - 1)
+        .unwrap_or_default()
+        .replace('\t', " ");
+    let gutter = " ".repeat(line_number.to_string().len());
+
+    if let Some(column) = err.column() {
+        eprintln!("{}--> {}:{}:{}", gutter, file_name, line_number, column);
+    } else {
+        eprintln!("{}--> {}:{}", gutter, file_name, line_number);
+    }
+    eprintln!("{} |", gutter);
+    eprintln!("{}", format!("{} | {}", line_number, line).trim_end());
+    if let Some(column) = err.column() {
+        // Full-width characters take up two columns in the terminal
+        let prefix: String = line.chars().take(column - 1).collect();
+        eprintln!("{} | {}^", gutter, " ".repeat(prefix.width()));
+    }
+}
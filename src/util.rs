@@ -1,5 +1,28 @@
 pub mod escape_sequences {
-    use std::{fmt, io};
+    use std::{cell::Cell, fmt, io};
+
+    thread_local! {
+        // Thread-local rather than global so that tests running in parallel don't affect each other
+        static ENABLED: Cell<bool> = const { Cell::new(true) };
+    }
+
+    /// Turns all escape sequences on or off, such as when the output is not a terminal.
+    pub fn set_enabled(enabled: bool) {
+        ENABLED.with(|cell| cell.set(enabled));
+    }
+
+    pub fn enabled() -> bool {
+        ENABLED.with(Cell::get)
+    }
+
+    /// Returns the escape sequence if escape sequences are enabled or nothing otherwise.
+    pub fn color(sequence: &'static str) -> &'static str {
+        if enabled() {
+            sequence
+        } else {
+            ""
+        }
+    }
 
     pub const WHITE_ON_DEFAULT: &str = "\x1b[97;49m";
     pub const BLACK_FOREGROUND: &str = "\x1b[30m";
@@ -14,13 +37,41 @@ pub mod escape_sequences {
         write!(f, "{}\x1b[48;5;{}m", BLACK_FOREGROUND, brightness)
     }
 
+    /// Moves the cursor up to draw over the last lines, which does nothing if escape sequences are disabled.
     pub fn cursor_up(f: &mut impl io::Write, n: usize) -> io::Result<()> {
-        write!(f, "\x1b[{}F", n)
+        if enabled() {
+            write!(f, "\x1b[{}F", n)
+        } else {
+            Ok(())
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
 pub struct Point {
     pub x: usize,
     pub y: usize,
 }
+
+impl std::fmt::Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML and XML.
+pub fn escape(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for char in str.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}